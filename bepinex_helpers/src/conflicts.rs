@@ -0,0 +1,68 @@
+use std::{fmt::Display, fs, path::Path};
+
+/// DLLs loaded by the game from its own folder that mod loaders hijack.
+/// BepInEx's doorstop uses `winhttp.dll`, so it isn't listed here.
+const PROXY_DLLS: &[&str] = &["version.dll", "winmm.dll", "dinput8.dll"];
+
+/// Something in the game folder that's known to break BepInEx or the game once it's installed
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GameWarning {
+    EasyAntiCheat,
+    BattlEye,
+    MelonLoader,
+    UnityModManager,
+    ProxyDll(String),
+}
+
+impl Display for GameWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameWarning::EasyAntiCheat => write!(f, "Protected by EasyAntiCheat"),
+            GameWarning::BattlEye => write!(f, "Protected by BattlEye"),
+            GameWarning::MelonLoader => write!(f, "MelonLoader is installed"),
+            GameWarning::UnityModManager => write!(f, "Unity Mod Manager is installed"),
+            GameWarning::ProxyDll(dll) => write!(f, "Unknown proxy DLL: {dll}"),
+        }
+    }
+}
+
+pub fn get_warnings(game_path: &Path, data_dir: Option<&Path>) -> Vec<GameWarning> {
+    let mut warnings = Vec::new();
+
+    let file_names: Vec<String> = fs::read_dir(game_path)
+        .map(|dir| {
+            dir.filter_map(Result::ok)
+                .map(|el| el.file_name().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default();
+    let has = |name: &str| file_names.iter().any(|f| f == name);
+
+    if has("easyanticheat") || has("start_protected_game.exe") {
+        warnings.push(GameWarning::EasyAntiCheat);
+    }
+    if has("battleye") || file_names.iter().any(|f| f.ends_with("_be.exe")) {
+        warnings.push(GameWarning::BattlEye);
+    }
+
+    let melon_loader = has("melonloader");
+    if melon_loader {
+        warnings.push(GameWarning::MelonLoader);
+    }
+
+    let umm_in_managed = data_dir
+        .map(|dir| dir.join("Managed").join("UnityModManager").exists())
+        .unwrap_or_default();
+    if has("unitymodmanager") || umm_in_managed {
+        warnings.push(GameWarning::UnityModManager);
+    }
+
+    for dll in PROXY_DLLS {
+        // version.dll is MelonLoader's own proxy, no need to report it twice
+        if has(dll) && !(melon_loader && *dll == "version.dll") {
+            warnings.push(GameWarning::ProxyDll(dll.to_string()));
+        }
+    }
+
+    warnings
+}
@@ -5,7 +5,13 @@ use std::{
     fs,
     path::{Path, PathBuf},
 };
-use steamlocate::SteamDir;
+
+use crate::{
+    conflicts::{get_warnings, GameWarning},
+    logs::{find_latest_log, get_player_log_paths, read_app_info, tail, AppInfo},
+    runtime::{get_runtime_details, RuntimeDetails},
+    steam::{get_shortcuts, get_steam_apps, get_steam_roots, proton_prefix},
+};
 
 #[macro_export]
 macro_rules! game_type {
@@ -52,10 +58,17 @@ impl Display for GameType {
 #[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd)]
 pub struct Game {
     pub name: String,
+    /// Steam app id, or the shortcut id for non-Steam games
+    pub app_id: Option<u32>,
+    /// Wine prefix Proton runs the game in, whether it was created or not
+    pub proton_prefix: Option<PathBuf>,
     pub arch: String,
     pub path: PathBuf,
     pub ty: Option<GameType>,
+    pub runtime: RuntimeDetails,
+    pub app_info: Option<AppInfo>,
     pub bepinex_version: Option<Version>,
+    pub warnings: Vec<GameWarning>,
 }
 
 impl Game {
@@ -71,6 +84,58 @@ impl Game {
         self.ty = ty;
     }
 
+    pub fn set_app_id(&mut self, app_id: Option<u32>) {
+        self.app_id = app_id;
+    }
+
+    pub fn set_proton_prefix(&mut self, proton_prefix: Option<PathBuf>) {
+        self.proton_prefix = proton_prefix;
+    }
+
+    pub fn set_app_info(&mut self, app_info: Option<AppInfo>) {
+        self.app_info = app_info;
+    }
+
+    pub fn set_runtime(&mut self, runtime: RuntimeDetails) {
+        self.runtime = runtime;
+    }
+
+    pub fn set_warnings(&mut self, warnings: Vec<GameWarning>) {
+        self.warnings = warnings;
+    }
+
+    /// Runs Unity detection on a game folder, returns `None` if it isn't a Unity game
+    pub fn from_path(name: String, path: &Path) -> Option<Game> {
+        if !path.join("UnityPlayer.dll").exists() {
+            return None;
+        }
+
+        let mut game = Game {
+            name,
+            app_id: None,
+            proton_prefix: None,
+            arch: "x64".to_owned(),
+            path: path.to_owned(),
+            bepinex_version: None,
+            ty: None,
+            runtime: RuntimeDetails::default(),
+            app_info: None,
+            warnings: Vec::new(),
+        };
+
+        let bie_ver = game.get_installed_bepinex_version();
+        let game_type = game.get_game_type();
+        let game_arch = game.get_game_arch();
+        game.set_bie(bie_ver);
+        game.set_ty(game_type);
+        game.set_arch(game_arch);
+        game.set_runtime(game.get_runtime_details());
+        game.set_app_info(game.get_app_info());
+        game.set_warnings(game.get_warnings());
+
+        Some(game)
+    }
+
     pub fn get_installed_bepinex_version(&self) -> Option<Version> {
         let core_path = self.path.join("BepInEx").join("core");
         match core_path.exists() {
@@ -86,8 +151,37 @@ impl Game {
         }
     }
 
+    /// Finds the `<Game>_Data` folder next to the executable
+    pub fn get_data_dir(&self) -> Option<PathBuf> {
+        fs::read_dir(&self.path)
+            .ok()?
+            .filter_map(Result::ok)
+            .find(|el| {
+                el.file_name().to_string_lossy().ends_with("_Data")
+                    && el.file_type().map(|t| t.is_dir()).unwrap_or_default()
+            })
+            .map(|el| el.path())
+    }
+
+    /// Unity names the executable after the data folder, which doesn't have to match the name
+    /// shown in Steam (and never does for non-Steam shortcuts)
+    pub fn get_exe_path(&self) -> PathBuf {
+        let by_name = self.path.join(format!("{}.exe", self.name));
+        if by_name.exists() {
+            return by_name;
+        }
+
+        self.get_data_dir()
+            .and_then(|dir| {
+                let file_name = dir.file_name()?.to_string_lossy().into_owned();
+                let exe = file_name.strip_suffix("_Data")?;
+                Some(self.path.join(format!("{exe}.exe")))
+            })
+            .unwrap_or(by_name)
+    }
+
     pub fn get_game_arch(&self) -> GameArch {
-        let path = &self.path.join(format!("{}.exe", &self.name));
+        let path = &self.get_exe_path();
         fs::read(path)
             .map(|bytes| {
                 let start =
@@ -102,27 +196,47 @@ impl Game {
             .unwrap_or_else(|_| GameArch::X64)
     }
 
+    pub fn get_runtime_details(&self) -> RuntimeDetails {
+        get_runtime_details(&self.path, self.get_data_dir().as_deref())
+    }
+
+    pub fn get_app_info(&self) -> Option<AppInfo> {
+        read_app_info(&self.get_data_dir()?)
+    }
+
+    /// Finds the most recent `Player.log`/`output_log.txt` of the game, including the ones
+    /// written inside a Proton prefix
+    pub fn find_player_log(&self) -> Option<PathBuf> {
+        let paths = get_player_log_paths(
+            self.get_data_dir().as_deref(),
+            self.app_info.as_ref(),
+            self.proton_prefix.as_deref(),
+        );
+        find_latest_log(&paths)
+    }
+
+    /// Last `lines` lines of the player log along with its path, for crash reports
+    pub fn tail_player_log(&self, lines: usize) -> Option<(PathBuf, String)> {
+        let path = self.find_player_log()?;
+        let text = tail(&path, lines).ok()?;
+        Some((path, text))
+    }
+
+    pub fn get_warnings(&self) -> Vec<GameWarning> {
+        get_warnings(&self.path, self.get_data_dir().as_deref())
+    }
+
     pub fn get_game_type(&self) -> Option<GameType> {
         let mono = "Managed";
         let il2cpp = "il2cpp_data";
 
-        match fs::read_dir(&self.path) {
-            Ok(dir) => {
-                let data_dir = dir.filter_map(Result::ok).find(|el| {
-                    el.file_name().to_str().unwrap().ends_with("_Data")
-                        && el.file_type().unwrap().is_dir()
-                });
-
-                let data_dir = data_dir.as_ref()?.path();
-                if data_dir.join(mono).exists() {
-                    Some(GameType::UnityMono)
-                } else if data_dir.join(il2cpp).exists() {
-                    Some(GameType::UnityIL2CPP)
-                } else {
-                    None
-                }
-            }
-            _ => None,
+        let data_dir = self.get_data_dir()?;
+        if data_dir.join(mono).exists() {
+            Some(GameType::UnityMono)
+        } else if data_dir.join(il2cpp).exists() {
+            Some(GameType::UnityIL2CPP)
+        } else {
+            None
         }
     }
 }
@@ -131,10 +245,15 @@ impl Default for Game {
     fn default() -> Self {
         Self {
             name: "Not selected".to_owned(),
+            app_id: None,
+            proton_prefix: None,
             arch: "x64".to_owned(),
             path: Default::default(),
             ty: None,
+            runtime: RuntimeDetails::default(),
+            app_info: None,
             bepinex_version: None,
+            warnings: Vec::new(),
         }
     }
 }
@@ -146,49 +265,64 @@ impl Display for Game {
 }
 
 pub fn get_unity_games() -> Result<Vec<Game>, Box<dyn error::Error>> {
-    let mut steamapps = SteamDir::locate().ok_or("Steam not found")?;
-    let apps = steamapps.apps();
-
-    let unity_games = apps
-        .iter()
-        .filter_map(|(_id, app)| app.as_ref())
-        .filter_map(|app| {
-            let path = Path::new(&app.path);
-            match path.join("UnityPlayer.dll").exists() {
-                true => {
-                    let mut game = Game {
-                        name: app.name.clone().unwrap_or_default(),
-                        arch: "x64".to_owned(),
-                        path: app.path.to_owned(),
-                        bepinex_version: None,
-                        ty: None,
-                    };
-
-                    let bie_ver = game.get_installed_bepinex_version();
-                    let game_type = game.get_game_type();
-                    let game_arch = game.get_game_arch();
-                    game.set_bie(bie_ver);
-                    game.set_ty(game_type);
-                    game.set_arch(game_arch);
-
-                    Some(game)
-                }
-                false => None,
+    get_unity_games_with_roots(&[])
+}
+
+/// Same as [`get_unity_games`], but also looks into user specified Steam roots.
+/// Games found in several roots (or both as an app and a shortcut) are only returned once.
+pub fn get_unity_games_with_roots(
+    extra_roots: &[PathBuf],
+) -> Result<Vec<Game>, Box<dyn error::Error>> {
+    let roots = get_steam_roots(extra_roots);
+    if roots.is_empty() {
+        return Err("Steam not found".into());
+    }
+
+    let mut unity_games: Vec<Game> = Vec::new();
+    let mut seen_paths: Vec<PathBuf> = Vec::new();
+
+    for root in roots.iter() {
+        let apps = get_steam_apps(root)
+            .into_iter()
+            .map(|app| (app.name, app.path, app.app_id, app.library));
+        // Proton keeps the prefixes of shortcuts in the Steam root
+        let shortcuts = get_shortcuts(root).into_iter().map(|shortcut| {
+            let path = shortcut.game_dir();
+            (shortcut.name, path, shortcut.app_id, root.to_owned())
+        });
+
+        for (name, path, app_id, library) in apps.chain(shortcuts) {
+            let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.to_owned());
+            if seen_paths.contains(&canonical) {
+                continue;
             }
-        })
-        .collect::<Vec<_>>();
+            seen_paths.push(canonical);
+
+            if let Some(mut game) = Game::from_path(name, &path) {
+                game.set_app_id(Some(app_id));
+                game.set_proton_prefix(Some(proton_prefix(&library, app_id)));
+                unity_games.push(game);
+            }
+        }
+    }
+
     Ok(unity_games)
 }
 
 pub fn get_dll_version(path: PathBuf) -> Result<Version, Box<dyn error::Error>> {
     let file = pelite::FileMap::open(path.as_path())?;
-    let img = pelite::PeFile::from_bytes(file.as_ref())?;
+    get_dll_version_from_bytes(file.as_ref())
+}
+
+/// Same as [`get_dll_version`] for a DLL that's already in memory, e.g. read from a zip
+pub fn get_dll_version_from_bytes(bytes: &[u8]) -> Result<Version, Box<dyn error::Error>> {
+    let img = pelite::PeFile::from_bytes(bytes)?;
     let resources = img.resources()?;
     let version_info = resources.version_info()?;
 
     let lang = version_info
         .translation()
-        .get(0)
+        .first()
         .ok_or("Failed to get lang")?;
 
     let strings = version_info.file_info().strings;
@@ -202,10 +336,10 @@ pub fn get_dll_version(path: PathBuf) -> Result<Version, Box<dyn error::Error>>
 
     // "Converts" 5.*.*.* into 5.*.* becuase BepInEx devs decided to add build num 💀
     if version.starts_with('5') && version.split('.').count() > 3 {
-        let ver = version.split('.').into_iter().collect::<Vec<&str>>()[0..3].join(".");
-        return Ok(Version::parse(&ver).unwrap());
+        let ver = version.split('.').collect::<Vec<&str>>()[0..3].join(".");
+        return Ok(Version::parse(&ver)?);
     }
 
     // TODO: Do some proper handling of invalid semver that bie has in older versions 💀
-    Ok(Version::parse(version).unwrap())
+    Ok(Version::parse(version)?)
 }
@@ -1 +1,6 @@
+pub mod conflicts;
 pub mod game;
+pub mod logs;
+pub mod runtime;
+pub mod steam;
+pub mod vdf;
@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Company and product names from `<Game>_Data/app.info`, Unity uses them for the
+/// `Player.log` folder
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AppInfo {
+    pub company: String,
+    pub product: String,
+}
+
+pub fn read_app_info(data_dir: &Path) -> Option<AppInfo> {
+    let text = fs::read_to_string(data_dir.join("app.info")).ok()?;
+    let mut lines = text.lines().map(str::trim);
+
+    let company = lines.next().filter(|l| !l.is_empty())?.to_owned();
+    let product = lines.next().filter(|l| !l.is_empty())?.to_owned();
+    Some(AppInfo { company, product })
+}
+
+const LOG_NAMES: &[&str] = &["Player.log", "output_log.txt"];
+
+/// Every place Unity might have written the player log to, whether it exists or not.
+/// `proton_prefix` is the game's Wine prefix when it runs through Proton.
+pub fn get_player_log_paths(
+    data_dir: Option<&Path>,
+    app_info: Option<&AppInfo>,
+    proton_prefix: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+
+    if let Some(info) = app_info {
+        if let Some(profile) = std::env::var_os("USERPROFILE").map(PathBuf::from) {
+            dirs.push(local_low(&profile, info));
+        }
+
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            dirs.push(
+                home.join(".config")
+                    .join("unity3d")
+                    .join(&info.company)
+                    .join(&info.product),
+            );
+        }
+
+        if let Some(prefix) = proton_prefix {
+            let profile = prefix.join("drive_c").join("users").join("steamuser");
+            dirs.push(local_low(&profile, info));
+        }
+    }
+
+    // Unity 5 and older write `output_log.txt` next to the game data
+    if let Some(data_dir) = data_dir {
+        dirs.push(data_dir.to_owned());
+    }
+
+    dirs.iter()
+        .flat_map(|dir| LOG_NAMES.iter().map(move |name| dir.join(name)))
+        .collect()
+}
+
+fn local_low(profile: &Path, info: &AppInfo) -> PathBuf {
+    profile
+        .join("AppData")
+        .join("LocalLow")
+        .join(&info.company)
+        .join(&info.product)
+}
+
+/// Most recently written log out of `paths`
+pub fn find_latest_log(paths: &[PathBuf]) -> Option<PathBuf> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path.to_owned())
+}
+
+/// Last `lines` lines of a log file
+pub fn tail(path: &Path, lines: usize) -> std::io::Result<String> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut tail = text.lines().rev().take(lines).collect::<Vec<_>>();
+    tail.reverse();
+    Ok(tail.join("\n"))
+}
@@ -0,0 +1,127 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+const IL2CPP_METADATA_MAGIC: u32 = 0xFAB11BAF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MonoFlavor {
+    Mono,
+    MonoBleedingEdge,
+}
+
+impl Display for MonoFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonoFlavor::Mono => write!(f, "Mono"),
+            MonoFlavor::MonoBleedingEdge => write!(f, "MonoBleedingEdge"),
+        }
+    }
+}
+
+/// Profile of the game's `mscorlib.dll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DotNetProfile {
+    Net20,
+    Net4x,
+}
+
+impl Display for DotNetProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotNetProfile::Net20 => write!(f, ".NET 2.0"),
+            DotNetProfile::Net4x => write!(f, ".NET 4.x"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuntimeDetails {
+    pub mono_flavor: Option<MonoFlavor>,
+    pub profile: Option<DotNetProfile>,
+    /// Version from the `global-metadata.dat` header, `None` for Mono games or when the
+    /// metadata is encrypted
+    pub metadata_version: Option<u32>,
+}
+
+impl Display for RuntimeDetails {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.mono_flavor, self.profile, self.metadata_version) {
+            (Some(flavor), Some(profile), _) => write!(f, "{flavor} ({profile})"),
+            (Some(flavor), None, _) => write!(f, "{flavor}"),
+            (None, _, Some(version)) => write!(f, "IL2CPP metadata v{version}"),
+            _ => write!(f, "Unknown"),
+        }
+    }
+}
+
+pub fn get_runtime_details(game_path: &Path, data_dir: Option<&Path>) -> RuntimeDetails {
+    let data_dir = match data_dir {
+        Some(dir) => dir,
+        None => return RuntimeDetails::default(),
+    };
+
+    let mono_flavor = get_mono_flavor(game_path, data_dir);
+    let profile = mono_flavor.and_then(|flavor| {
+        get_mscorlib_profile(data_dir.join("Managed").join("mscorlib.dll")).or(
+            // Unity doesn't always stamp a version on mscorlib, fall back to what the runtime ships
+            match flavor {
+                MonoFlavor::Mono => Some(DotNetProfile::Net20),
+                MonoFlavor::MonoBleedingEdge => Some(DotNetProfile::Net4x),
+            },
+        )
+    });
+    let metadata_version = get_metadata_version(
+        data_dir
+            .join("il2cpp_data")
+            .join("Metadata")
+            .join("global-metadata.dat"),
+    );
+
+    RuntimeDetails {
+        mono_flavor,
+        profile,
+        metadata_version,
+    }
+}
+
+fn get_mono_flavor(game_path: &Path, data_dir: &Path) -> Option<MonoFlavor> {
+    if !data_dir.join("Managed").exists() {
+        return None;
+    }
+
+    if game_path.join("MonoBleedingEdge").exists() {
+        Some(MonoFlavor::MonoBleedingEdge)
+    } else if game_path.join("Mono").exists() || data_dir.join("Mono").exists() {
+        Some(MonoFlavor::Mono)
+    } else {
+        None
+    }
+}
+
+fn get_mscorlib_profile(path: PathBuf) -> Option<DotNetProfile> {
+    let file = pelite::FileMap::open(path.as_path()).ok()?;
+    let img = pelite::PeFile::from_bytes(file.as_ref()).ok()?;
+    let resources = img.resources().ok()?;
+    let fixed = resources.version_info().ok()?.fixed()?;
+
+    match fixed.dwFileVersion.Major {
+        2 => Some(DotNetProfile::Net20),
+        4 => Some(DotNetProfile::Net4x),
+        _ => None,
+    }
+}
+
+fn get_metadata_version(path: PathBuf) -> Option<u32> {
+    let mut header = [0u8; 8];
+    File::open(path).ok()?.read_exact(&mut header).ok()?;
+
+    let magic = u32::from_le_bytes(header[0..4].try_into().ok()?);
+    match magic == IL2CPP_METADATA_MAGIC {
+        true => Some(u32::from_le_bytes(header[4..8].try_into().ok()?)),
+        false => None,
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use steamlocate::SteamDir;
+
+use crate::vdf::{self, VdfValue};
+
+/// Steam app installed in one of the library folders of a Steam root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamApp {
+    pub app_id: u32,
+    pub name: String,
+    pub path: PathBuf,
+    /// Library folder the app is installed in
+    pub library: PathBuf,
+}
+
+/// Non-Steam game added to the Steam library by the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    pub app_id: u32,
+    pub name: String,
+    pub exe: PathBuf,
+    pub start_dir: PathBuf,
+}
+
+impl Shortcut {
+    fn from_vdf(value: &VdfValue) -> Option<Self> {
+        let name = value.get("AppName")?.as_str()?.to_owned();
+        let raw_exe = value.get("Exe")?.as_str()?;
+        let exe = PathBuf::from(unquote(raw_exe));
+        let start_dir = value
+            .get("StartDir")
+            .and_then(VdfValue::as_str)
+            .map(unquote)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| exe.parent().map(Path::to_path_buf))?;
+        let app_id = value
+            .get("appid")
+            .and_then(VdfValue::as_int)
+            .map(|id| id as u32)
+            .unwrap_or_else(|| legacy_app_id(raw_exe, &name));
+
+        Some(Shortcut {
+            app_id,
+            name,
+            exe,
+            start_dir,
+        })
+    }
+
+    /// Folder the game lives in. `StartDir` usually points there, but fall back to the exe's
+    /// folder when it doesn't, e.g. when the game is started through a launcher.
+    pub fn game_dir(&self) -> PathBuf {
+        match self.start_dir.join("UnityPlayer.dll").exists() {
+            true => self.start_dir.to_owned(),
+            false => self
+                .exe
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| self.start_dir.to_owned()),
+        }
+    }
+}
+
+/// Older `shortcuts.vdf` files don't store the id, Steam derives it from the quoted exe and the
+/// name like this
+fn legacy_app_id(exe: &str, name: &str) -> u32 {
+    let mut crc = !0u32;
+    for byte in exe.bytes().chain(name.bytes()) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB88320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc | 0x80000000
+}
+
+/// Wine prefix Proton creates for an app (or shortcut) in `steamapps/compatdata` of a library
+pub fn proton_prefix(library: &Path, app_id: u32) -> PathBuf {
+    library
+        .join("steamapps")
+        .join("compatdata")
+        .join(app_id.to_string())
+        .join("pfx")
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches('"')
+}
+
+/// Reads `userdata/<id>/config/shortcuts.vdf` of every Steam user in the given Steam root.
+pub fn get_shortcuts(steam_path: &Path) -> Vec<Shortcut> {
+    let user_dirs = match fs::read_dir(steam_path.join("userdata")) {
+        Ok(dir) => dir,
+        Err(_) => return Vec::new(),
+    };
+
+    user_dirs
+        .filter_map(Result::ok)
+        .map(|user| user.path().join("config").join("shortcuts.vdf"))
+        .filter_map(|path| fs::read(path).ok())
+        .filter_map(|bytes| vdf::parse_binary(&bytes).ok())
+        .flat_map(|root| {
+            root.get("shortcuts")
+                .map(|shortcuts| {
+                    shortcuts
+                        .entries()
+                        .iter()
+                        .filter_map(|(_, s)| Shortcut::from_vdf(s))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Steam install locations that aren't found by `SteamDir::locate`, relative to `$HOME`
+#[cfg(target_os = "linux")]
+const LINUX_STEAM_ROOTS: &[&str] = &[
+    ".steam/steam",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+    "snap/steam/common/.local/share/Steam",
+    "snap/steam/common/.steam/steam",
+];
+
+/// Every Steam root that exists on this machine: the default install, the known Flatpak and
+/// Snap locations and any user specified roots. Roots reachable through symlinks
+/// (e.g. `~/.steam/steam`) are only returned once.
+pub fn get_steam_roots(extra_roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    if let Some(steam_dir) = SteamDir::locate() {
+        candidates.push(steam_dir.path);
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        candidates.extend(LINUX_STEAM_ROOTS.iter().map(|root| home.join(root)));
+    }
+
+    candidates.extend(extra_roots.iter().cloned());
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        if !candidate.join("steamapps").is_dir() {
+            continue;
+        }
+        let canonical = fs::canonicalize(&candidate).unwrap_or(candidate);
+        if !roots.contains(&canonical) {
+            roots.push(canonical);
+        }
+    }
+    roots
+}
+
+/// Library folders listed in `steamapps/libraryfolders.vdf`, including the root itself
+pub fn get_library_folders(steam_path: &Path) -> Vec<PathBuf> {
+    let mut folders = vec![steam_path.to_owned()];
+
+    let libraries = fs::read_to_string(steam_path.join("steamapps").join("libraryfolders.vdf"))
+        .ok()
+        .and_then(|text| vdf::parse_text(&text).ok());
+
+    if let Some(libraries) = libraries.as_ref().and_then(|l| l.get("libraryfolders")) {
+        for (_, library) in libraries.entries() {
+            // Old format is `"1" "D:\\SteamLibrary"`, new one is a map with a `path` key
+            let path = match library {
+                VdfValue::String(path) => Some(path.as_str()),
+                VdfValue::Map(_) => library.get("path").and_then(VdfValue::as_str),
+                _ => None,
+            };
+            if let Some(path) = path.map(PathBuf::from) {
+                if path.join("steamapps").is_dir() && !folders.contains(&path) {
+                    folders.push(path);
+                }
+            }
+        }
+    }
+
+    folders
+}
+
+/// Reads `appmanifest_*.acf` files of every library folder of a Steam root
+pub fn get_steam_apps(steam_path: &Path) -> Vec<SteamApp> {
+    get_library_folders(steam_path)
+        .iter()
+        .flat_map(|library| {
+            let steamapps = library.join("steamapps");
+            fs::read_dir(&steamapps)
+                .map(|dir| {
+                    dir.filter_map(Result::ok)
+                        .map(|el| el.path())
+                        .filter(|path| {
+                            path.file_name()
+                                .map(|n| n.to_string_lossy().starts_with("appmanifest_"))
+                                .unwrap_or_default()
+                        })
+                        .filter_map(|path| read_app_manifest(library, &path))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn read_app_manifest(library: &Path, manifest: &Path) -> Option<SteamApp> {
+    let text = fs::read_to_string(manifest).ok()?;
+    let root = vdf::parse_text(&text).ok()?;
+    let state = root.get("AppState")?;
+
+    let app_id = state.get("appid")?.as_str()?.parse().ok()?;
+    let install_dir = state.get("installdir")?.as_str()?;
+    let name = state
+        .get("name")
+        .and_then(VdfValue::as_str)
+        .unwrap_or(install_dir)
+        .to_owned();
+
+    Some(SteamApp {
+        app_id,
+        name,
+        path: library.join("steamapps").join("common").join(install_dir),
+        library: library.to_owned(),
+    })
+}
@@ -0,0 +1,236 @@
+use std::{error, fmt::Display};
+
+const TYPE_MAP: u8 = 0x00;
+const TYPE_STRING: u8 = 0x01;
+const TYPE_INT: u8 = 0x02;
+const TYPE_FLOAT: u8 = 0x03;
+const TYPE_UINT64: u8 = 0x07;
+const TYPE_MAP_END: u8 = 0x08;
+const TYPE_INT64: u8 = 0x0A;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdfValue {
+    Map(Vec<(String, VdfValue)>),
+    String(String),
+    Int(i32),
+    Float(f32),
+    UInt64(u64),
+}
+
+impl VdfValue {
+    /// Looks up a key in a map, ignoring case since Steam isn't consistent about it
+    /// (e.g. `AppName` vs `appname` in shortcuts.vdf).
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, VdfValue)] {
+        match self {
+            VdfValue::Map(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            VdfValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VdfError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for VdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid VDF at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl error::Error for VdfError {}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn error(&self, message: &str) -> VdfError {
+        VdfError {
+            offset: self.pos,
+            message: message.to_owned(),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VdfError> {
+        let end = self.pos + len;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, VdfError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn string(&mut self) -> Result<String, VdfError> {
+        let len = self.bytes[self.pos..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| self.error("unterminated string"))?;
+        let s = String::from_utf8_lossy(self.take(len)?).into_owned();
+        self.pos += 1;
+        Ok(s)
+    }
+
+    fn map(&mut self) -> Result<Vec<(String, VdfValue)>, VdfError> {
+        let mut entries = Vec::new();
+        loop {
+            let ty = self.byte()?;
+            if ty == TYPE_MAP_END {
+                return Ok(entries);
+            }
+
+            let key = self.string()?;
+            let value = match ty {
+                TYPE_MAP => VdfValue::Map(self.map()?),
+                TYPE_STRING => VdfValue::String(self.string()?),
+                TYPE_INT => VdfValue::Int(i32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+                TYPE_FLOAT => {
+                    VdfValue::Float(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+                }
+                TYPE_UINT64 | TYPE_INT64 => {
+                    VdfValue::UInt64(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+                }
+                _ => return Err(self.error(&format!("unknown value type {ty:#04x}"))),
+            };
+            entries.push((key, value));
+        }
+    }
+}
+
+/// Parses Steam's binary KeyValues format, used by `shortcuts.vdf`.
+pub fn parse_binary(bytes: &[u8]) -> Result<VdfValue, VdfError> {
+    let mut reader = BinaryReader { bytes, pos: 0 };
+    let mut entries = Vec::new();
+
+    // The root is an implicit map that isn't always closed by a trailing 0x08
+    while reader.pos < bytes.len() {
+        match reader.byte()? {
+            TYPE_MAP => {
+                let key = reader.string()?;
+                entries.push((key, VdfValue::Map(reader.map()?)));
+            }
+            TYPE_MAP_END => break,
+            ty => return Err(reader.error(&format!("unexpected root type {ty:#04x}"))),
+        }
+    }
+
+    Ok(VdfValue::Map(entries))
+}
+
+struct TextReader<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    pos: usize,
+}
+
+impl<'a> TextReader<'a> {
+    fn error(&self, message: &str) -> VdfError {
+        VdfError {
+            offset: self.pos,
+            message: message.to_owned(),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<TextToken>, VdfError> {
+        while let Some((pos, c)) = self.chars.next() {
+            self.pos = pos;
+            match c {
+                '{' => return Ok(Some(TextToken::Open)),
+                '}' => return Ok(Some(TextToken::Close)),
+                '"' => return self.quoted().map(|s| Some(TextToken::String(s))),
+                '/' if matches!(self.chars.peek(), Some((_, '/'))) => {
+                    for (_, c) in self.chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                c if c.is_whitespace() => {}
+                _ => return Err(self.error("unquoted token")),
+            }
+        }
+        Ok(None)
+    }
+
+    fn quoted(&mut self) -> Result<String, VdfError> {
+        let mut s = String::new();
+        while let Some((pos, c)) = self.chars.next() {
+            self.pos = pos;
+            match c {
+                '"' => return Ok(s),
+                '\\' => match self.chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, escaped)) => s.push(escaped),
+                    None => break,
+                },
+                _ => s.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn map(&mut self, nested: bool) -> Result<Vec<(String, VdfValue)>, VdfError> {
+        let mut entries = Vec::new();
+        loop {
+            let key = match self.next_token()? {
+                Some(TextToken::String(key)) => key,
+                Some(TextToken::Close) if nested => return Ok(entries),
+                None if !nested => return Ok(entries),
+                _ => return Err(self.error("expected key")),
+            };
+            let value = match self.next_token()? {
+                Some(TextToken::String(value)) => VdfValue::String(value),
+                Some(TextToken::Open) => VdfValue::Map(self.map(true)?),
+                _ => return Err(self.error("expected value")),
+            };
+            entries.push((key, value));
+        }
+    }
+}
+
+enum TextToken {
+    Open,
+    Close,
+    String(String),
+}
+
+/// Parses Steam's text KeyValues format, used by `libraryfolders.vdf` and `appmanifest_*.acf`.
+pub fn parse_text(text: &str) -> Result<VdfValue, VdfError> {
+    let mut reader = TextReader {
+        chars: text.char_indices().peekable(),
+        pos: 0,
+    };
+    Ok(VdfValue::Map(reader.map(false)?))
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+
+use bepinex_helpers::conflicts::{get_warnings, GameWarning};
+
+use common::{temp_dir, write};
+
+#[test]
+fn warns_about_conflicts() {
+    let game = temp_dir("conflicts");
+    fs::create_dir_all(game.join("EasyAntiCheat")).unwrap();
+    fs::create_dir_all(game.join("MelonLoader")).unwrap();
+    write(&game.join("Game_BE.exe"), b"");
+    write(&game.join("version.dll"), b"");
+    write(&game.join("WINMM.dll"), b"");
+    fs::create_dir_all(game.join("Game_Data/Managed/UnityModManager")).unwrap();
+
+    assert_eq!(
+        get_warnings(&game, Some(&game.join("Game_Data"))),
+        [
+            GameWarning::EasyAntiCheat,
+            GameWarning::BattlEye,
+            GameWarning::MelonLoader,
+            GameWarning::UnityModManager,
+            // version.dll belongs to MelonLoader
+            GameWarning::ProxyDll("winmm.dll".to_owned()),
+        ]
+    );
+
+    fs::remove_dir_all(&game).unwrap();
+    fs::create_dir_all(&game).unwrap();
+    write(&game.join("winhttp.dll"), b"");
+    write(&game.join("version.dll"), b"");
+    assert_eq!(
+        get_warnings(&game, None),
+        [GameWarning::ProxyDll("version.dll".to_owned())]
+    );
+
+    fs::remove_dir_all(game).unwrap();
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::{fs, path::Path, thread, time::Duration};
+
+use bepinex_helpers::{
+    logs::{find_latest_log, get_player_log_paths, read_app_info, tail, AppInfo},
+    steam::proton_prefix,
+};
+
+use common::{temp_dir, write};
+
+#[test]
+fn finds_logs_in_the_proton_prefix() {
+    let dir = temp_dir("logs");
+    let data = dir.join("Game_Data");
+    write(&data.join("app.info"), "Some Company\nSome Game\n");
+    let info = read_app_info(&data).unwrap();
+    assert_eq!(
+        info,
+        AppInfo {
+            company: "Some Company".to_owned(),
+            product: "Some Game".to_owned(),
+        }
+    );
+
+    // Prefixes live in the library the game is installed in, not next to the game
+    let prefix = proton_prefix(&dir.join("library"), 2147483649);
+    assert_eq!(
+        prefix,
+        Path::new(&dir).join("library/steamapps/compatdata/2147483649/pfx")
+    );
+    let paths = get_player_log_paths(Some(&data), Some(&info), Some(&prefix));
+    let proton_log =
+        prefix.join("drive_c/users/steamuser/AppData/LocalLow/Some Company/Some Game/Player.log");
+    assert!(paths.contains(&proton_log));
+    assert!(paths.contains(&data.join("output_log.txt")));
+
+    let library = dir.join("library");
+    let without_prefix = get_player_log_paths(Some(&data), Some(&info), None);
+    assert!(!without_prefix.iter().any(|path| path.starts_with(&library)));
+
+    write(&data.join("output_log.txt"), "old");
+    thread::sleep(Duration::from_millis(20));
+    write(&proton_log, "first\nsecond\nthird\n");
+    assert_eq!(find_latest_log(&paths), Some(proton_log.clone()));
+    assert_eq!(tail(&proton_log, 2).unwrap(), "second\nthird");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn incomplete_app_info_is_ignored() {
+    let dir = temp_dir("app-info");
+    write(&dir.join("app.info"), "Only Company\n");
+    assert_eq!(read_app_info(&dir), None);
+    assert_eq!(read_app_info(&dir.join("missing")), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::fs;
+
+use bepinex_helpers::runtime::{get_runtime_details, DotNetProfile, MonoFlavor, RuntimeDetails};
+
+use common::{temp_dir, write};
+
+#[test]
+fn detects_mono_runtime() {
+    let game = temp_dir("runtime-mono");
+    let data = game.join("Game_Data");
+    fs::create_dir_all(game.join("MonoBleedingEdge")).unwrap();
+    // Not a PE file, so the profile comes from the runtime
+    write(&data.join("Managed/mscorlib.dll"), b"not a dll");
+
+    let details = get_runtime_details(&game, Some(&data));
+    assert_eq!(
+        details,
+        RuntimeDetails {
+            mono_flavor: Some(MonoFlavor::MonoBleedingEdge),
+            profile: Some(DotNetProfile::Net4x),
+            metadata_version: None,
+        }
+    );
+    assert_eq!(details.to_string(), "MonoBleedingEdge (.NET 4.x)");
+
+    fs::remove_dir_all(game.join("MonoBleedingEdge")).unwrap();
+    fs::create_dir_all(data.join("Mono")).unwrap();
+    let details = get_runtime_details(&game, Some(&data));
+    assert_eq!(details.mono_flavor, Some(MonoFlavor::Mono));
+    assert_eq!(details.profile, Some(DotNetProfile::Net20));
+
+    fs::remove_dir_all(game).unwrap();
+}
+
+#[test]
+fn reads_il2cpp_metadata_version() {
+    let game = temp_dir("runtime-il2cpp");
+    let data = game.join("Game_Data");
+    let metadata = data.join("il2cpp_data/Metadata/global-metadata.dat");
+    let mut header = 0xFAB11BAFu32.to_le_bytes().to_vec();
+    header.extend_from_slice(&29u32.to_le_bytes());
+    write(&metadata, &header);
+
+    let details = get_runtime_details(&game, Some(&data));
+    assert_eq!(details.mono_flavor, None);
+    assert_eq!(details.metadata_version, Some(29));
+    assert_eq!(details.to_string(), "IL2CPP metadata v29");
+
+    // Encrypted metadata doesn't start with the magic
+    write(&metadata, [0xFF; 8]);
+    assert_eq!(
+        get_runtime_details(&game, Some(&data)).metadata_version,
+        None
+    );
+    write(&metadata, [0xAF, 0x1B]);
+    assert_eq!(
+        get_runtime_details(&game, Some(&data)).metadata_version,
+        None
+    );
+    assert_eq!(get_runtime_details(&game, None), RuntimeDetails::default());
+
+    fs::remove_dir_all(game).unwrap();
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+
+use bepinex_helpers::steam::{get_library_folders, get_steam_apps, get_steam_roots};
+
+use common::{temp_dir, write};
+
+#[test]
+fn reads_library_folders_and_apps() {
+    let dir = temp_dir("libraries");
+    let (steam, old, new) = (dir.join("steam"), dir.join("old"), dir.join("new"));
+    for library in [&steam, &old, &new] {
+        fs::create_dir_all(library.join("steamapps")).unwrap();
+    }
+    let missing = dir.join("missing");
+    // Old format next to the new one, plus a library that's gone and the root itself
+    write(
+        &steam.join("steamapps/libraryfolders.vdf"),
+        format!(
+            r#""libraryfolders" {{
+                "contentstatsid" "-1"
+                "1" "{}"
+                "2" {{ "path" "{}" }}
+                "3" {{ "path" "{}" }}
+                "4" {{ "path" "{}" }}
+            }}"#,
+            old.display(),
+            new.display(),
+            missing.display(),
+            steam.display()
+        ),
+    );
+    assert_eq!(
+        get_library_folders(&steam),
+        [steam.clone(), old.clone(), new.clone()]
+    );
+
+    write(
+        &new.join("steamapps/appmanifest_1234.acf"),
+        r#""AppState" { "appid" "1234" "name" "Some Game" "installdir" "SomeGame" }"#,
+    );
+    write(
+        &old.join("steamapps/appmanifest_5678.acf"),
+        r#""AppState" { "appid" "5678" "installdir" "Unnamed" }"#,
+    );
+    write(&old.join("steamapps/appmanifest_9.acf"), r#""AppState" {"#);
+    let mut apps = get_steam_apps(&steam);
+    apps.sort_by_key(|app| app.app_id);
+    assert_eq!(apps.len(), 2);
+    assert_eq!(apps[0].name, "Some Game");
+    assert_eq!(apps[0].path, new.join("steamapps/common/SomeGame"));
+    assert_eq!(apps[1].name, "Unnamed");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn only_returns_existing_roots_once() {
+    let dir = temp_dir("roots");
+    fs::create_dir_all(dir.join("steam/steamapps")).unwrap();
+    fs::create_dir_all(dir.join("empty")).unwrap();
+
+    let extra = [dir.join("steam"), dir.join("steam/."), dir.join("empty")];
+    let roots = get_steam_roots(&extra);
+    let ours: Vec<_> = roots.iter().filter(|root| root.starts_with(&dir)).collect();
+    assert_eq!(ours, [&fs::canonicalize(dir.join("steam")).unwrap()]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
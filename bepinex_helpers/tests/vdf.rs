@@ -0,0 +1,103 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use bepinex_helpers::{
+    steam::{get_shortcuts, Shortcut},
+    vdf::{parse_binary, parse_text, VdfValue},
+};
+
+use common::{fixture, temp_dir, write};
+
+#[test]
+fn parses_binary_shortcuts() {
+    let bytes = fs::read(fixture("shortcuts.vdf")).unwrap();
+    let root = parse_binary(&bytes).unwrap();
+
+    let shortcuts = root.get("shortcuts").unwrap();
+    assert_eq!(shortcuts.entries().len(), 2);
+    let game = shortcuts.get("0").unwrap();
+    assert_eq!(game.get("appid").and_then(VdfValue::as_int), Some(-1234567890));
+    assert_eq!(game.get("isHidden"), Some(&VdfValue::Int(0)));
+    assert_eq!(game.get("appname").and_then(VdfValue::as_str), Some("Game"));
+    let tags = game.get("tags").unwrap();
+    assert_eq!(tags.get("0").and_then(VdfValue::as_str), Some("favorite"));
+}
+
+#[test]
+fn truncated_binary_is_an_error() {
+    let bytes = fs::read(fixture("shortcuts.vdf")).unwrap();
+    assert!(parse_binary(&bytes[..20]).is_err());
+    assert!(parse_binary(&[0x00, b'a']).is_err());
+    assert!(parse_binary(&[0x05]).is_err());
+
+    // Cutting the file anywhere must not panic
+    for len in 0..bytes.len() {
+        let _ = parse_binary(&bytes[..len]);
+    }
+}
+
+#[test]
+fn parses_text_with_escapes() {
+    let text = r#"
+        // comment
+        "libraryfolders"
+        {
+            "0"
+            {
+                "path"    "C:\\Program Files (x86)\\Steam"
+                "label"   "say \"hi\"\tthere"
+            }
+        }
+    "#;
+    let root = parse_text(text).unwrap();
+    let library = root.get("libraryfolders").unwrap().get("0").unwrap();
+    assert_eq!(
+        library.get("path").and_then(VdfValue::as_str),
+        Some(r"C:\Program Files (x86)\Steam")
+    );
+    assert_eq!(
+        library.get("label").and_then(VdfValue::as_str),
+        Some("say \"hi\"\tthere")
+    );
+
+    assert!(parse_text(r#""key" { "unterminated"#).is_err());
+    assert!(parse_text(r#""key" value"#).is_err());
+}
+
+#[test]
+fn reads_shortcuts_of_every_user() {
+    let steam = temp_dir("shortcuts");
+    let bytes = fs::read(fixture("shortcuts.vdf")).unwrap();
+    write(&steam.join("userdata/1/config/shortcuts.vdf"), &bytes);
+    write(&steam.join("userdata/2/config/shortcuts.vdf"), b"\x00broken");
+
+    // The entry without an `Exe` is skipped, so is the unreadable file
+    assert_eq!(
+        get_shortcuts(&steam),
+        [Shortcut {
+            app_id: 3060399406,
+            name: "Game".to_owned(),
+            exe: PathBuf::from("/games/Game/Game.exe"),
+            start_dir: PathBuf::from("/games/Game/"),
+        }]
+    );
+
+    fs::remove_dir_all(steam).unwrap();
+}
+
+#[test]
+fn computes_missing_shortcut_ids() {
+    let steam = temp_dir("legacy-shortcuts");
+    let mut bytes = b"\x00shortcuts\0\x000\0".to_vec();
+    bytes.extend_from_slice(b"\x01AppName\0Game\0\x01Exe\0\"/games/Game/Game.exe\"\0");
+    bytes.extend_from_slice(b"\x08\x08\x08");
+    write(&steam.join("userdata/1/config/shortcuts.vdf"), &bytes);
+
+    let shortcuts = get_shortcuts(&steam);
+    assert_eq!(shortcuts.len(), 1);
+    // CRC-32 of the quoted exe and the name, with the top bit set
+    assert_eq!(shortcuts[0].app_id, 2950080196);
+
+    fs::remove_dir_all(steam).unwrap();
+}
@@ -1,89 +1,323 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
 
-use bepinex_helpers::game::{get_unity_games, Game, GameType};
+use bepinex_helpers::game::{get_unity_games_with_roots, Game};
 use bepinex_sources::{
     bepinex::{AssetDownloader, BepInEx, BepInExRelease, ReleaseFlavor},
-    builds::BuildsApi,
-    github::GitHubApi,
-    version::VersionExt,
+    cache::ArtifactCache,
+    changelog::Changelog,
+    client::SourcesClient,
+    download::{CancelToken, Cancelled, DownloadedArchive, Downloader, Progress},
+    extract::ExtractionPlan,
+    plan::InstallPlan,
+    rules::Rules,
+    version::{ReleaseRequirement, VersionExt},
 };
 use eframe::{
-    egui::{CentralPanel, ComboBox, Direction, FontFamily::Proportional, FontId, TextStyle, Ui},
+    egui::{
+        Align2, Button, CentralPanel, CollapsingHeader, ComboBox, Context, Direction,
+        FontFamily::Proportional, FontId, ProgressBar, ScrollArea, TextStyle, Ui, Window,
+    },
     App,
 };
 use egui_extras::{Size, StripBuilder};
 use egui_toast::{ToastOptions, Toasts};
-use parking_lot::Once;
-
-use crate::{MIN_IL2CPP_STABLE_VERSION, MIN_SUPPORTED_STABLE_VERSION};
 
-static INIT_BIE: Once = Once::new();
+use crate::settings::Settings;
 
 #[derive(Default)]
 pub struct Installer {
+    pub settings: Settings,
     pub release_flavor: ReleaseFlavor,
     pub bepinex: BepInEx,
     pub selected_bie: Option<BepInExRelease>,
     pub games: Vec<Game>,
     pub selected_game: Option<Game>,
-    pub dl_promise: Option<poll_promise::Promise<anyhow::Result<()>>>,
-    pub fetch_promises: HashMap<String, poll_promise::Promise<Vec<BepInExRelease>>>,
-    pub shown_toast: bool,
+    pub dl_promise: Option<poll_promise::Promise<anyhow::Result<ExtractionPlan>>>,
+    /// Progress of the running download, the latest value is kept in `dl_progress`
+    pub dl_progress_rx: Option<Receiver<Progress>>,
+    pub dl_progress: Option<Progress>,
+    pub dl_cancel: CancelToken,
+    pub fetch_promise: Option<poll_promise::Promise<BepInEx>>,
+    /// Dry run of the install, running while the archive is downloaded
+    pub plan_promise:
+        Option<poll_promise::Promise<anyhow::Result<(InstallPlan, DownloadedArchive)>>>,
+    /// Plan and changes shown before installing, `Some` while the dialog is open
+    pub confirm_install: Option<(InstallPlan, Changelog)>,
+    /// Archive downloaded for the plan, extracted once it's confirmed
+    pub planned_archive: Option<DownloadedArchive>,
+    pub client: SourcesClient,
+    /// Problems with the settings or rules file, shown once releases are fetched
+    pub startup_errors: Vec<String>,
+    /// Release to preselect, from `--version <req>`
+    pub version_req: Option<ReleaseRequirement>,
+    /// Downloaded archives, reused when installing the same release again
+    pub artifacts: Option<ArtifactCache>,
+    /// Stale cached releases are being refetched in the background
+    pub revalidating: bool,
+    revalidated: bool,
+}
+
+fn format_mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / 1024.0 / 1024.0)
+}
+
+/// Rough age for humans, e.g. `5 min` or `3 h`
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    match minutes {
+        0 => "less than a minute".to_owned(),
+        1..=59 => format!("{} min", minutes),
+        60..=2879 => format!("{} h", minutes / 60),
+        _ => format!("{} days", minutes / 60 / 24),
+    }
 }
 
 impl Installer {
-    pub fn new() -> Self {
-        let mut new_app = Self::default();
-        let bie = BepInEx::default();
-
-        let gh_promise = poll_promise::Promise::spawn_thread("gh_fetch", || {
-            let mut gh = GitHubApi::new("BepInEx", "BepInEx");
-            gh.set_pre_releases(true);
-            gh.set_min_tag(Some(MIN_SUPPORTED_STABLE_VERSION.clone()));
-
-            gh.get_all()
-                .unwrap_or_default()
-                .into_iter()
-                .map(BepInExRelease::from)
-                .collect::<Vec<_>>()
-        });
-        new_app.fetch_promises.insert("gh_fetch".into(), gh_promise);
-
-        let be_promise = poll_promise::Promise::spawn_thread("be_fetch", || {
-            let be = BuildsApi::new("https://builds.bepinex.dev");
-            be.get_builds()
-                .unwrap_or_default()
-                .into_iter()
-                .map(BepInExRelease::from)
-                .collect::<Vec<_>>()
+    pub fn new(version_req: Option<ReleaseRequirement>) -> Self {
+        let mut new_app = Self {
+            version_req,
+            ..Self::default()
+        };
+        let settings = match Settings::load() {
+            Ok(settings) => settings,
+            Err(e) => {
+                new_app
+                    .startup_errors
+                    .push(format!("Invalid settings file, using defaults: {}", e));
+                Settings::default()
+            }
+        };
+
+        let rules = match Settings::load_rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                new_app
+                    .startup_errors
+                    .push(format!("Invalid rules file, using defaults: {}", e));
+                Rules::bundled()
+            }
+        };
+        let client = match SourcesClient::new(settings.network.clone()) {
+            Ok(client) => client,
+            Err(e) => {
+                new_app
+                    .startup_errors
+                    .push(format!("Invalid network settings, using defaults: {}", e));
+                SourcesClient::default()
+            }
+        };
+        new_app.client = client.clone();
+        new_app.artifacts = settings.artifact_cache();
+        let mut bie = settings.bepinex(rules, &client);
+        let fetch_promise = poll_promise::Promise::spawn_thread("fetch", move || {
+            bie.fetch();
+            bie
         });
-        new_app.fetch_promises.insert("be_fetch".into(), be_promise);
+        new_app.fetch_promise = Some(fetch_promise);
 
-        let mut games = get_unity_games().unwrap_or_default();
+        let mut games = get_unity_games_with_roots(&settings.steam_roots).unwrap_or_default();
         games.sort();
 
+        new_app.settings = settings;
         new_app.games = games;
-        new_app.bepinex = bie;
-        new_app.selected_bie = new_app.bepinex.latest();
 
         new_app
     }
 
-    fn fetch(&mut self) {
-        let gh_promise = self.fetch_promises.get("gh_fetch").unwrap();
-        let be_promise = self.fetch_promises.get("be_fetch").unwrap();
-        if let (Some(gh_releases), Some(be_releases)) = (gh_promise.ready(), be_promise.ready()) {
-            INIT_BIE.call_once(|| {
-                let mut releases: Vec<BepInExRelease> = Vec::new();
-                releases.extend(gh_releases.iter().cloned());
-                releases.extend(be_releases.iter().cloned());
+    fn fetch(&mut self, toasts: &mut Toasts, options: ToastOptions) {
+        let fetched = self
+            .fetch_promise
+            .as_ref()
+            .and_then(|promise| promise.ready())
+            .cloned();
+        if let Some(bie) = fetched {
+            for error in bie.errors.iter() {
+                toasts.error(format!("Failed to fetch releases from {}", error), options);
+            }
+            for error in self.startup_errors.drain(..) {
+                toasts.error(error, options);
+            }
+
+            self.bepinex = bie;
+            // Keep what was picked while stale releases were refetched
+            let keep_selected = self
+                .selected_bie
+                .as_ref()
+                .map(|selected| self.bepinex.releases.contains(selected))
+                .unwrap_or(false);
+            if !keep_selected {
+                self.select_latest();
+            }
+            self.fetch_promise = None;
+            self.revalidating = false;
+
+            if !self.revalidated && self.bepinex.needs_revalidation() {
+                self.revalidated = true;
+                self.revalidating = true;
+                let mut bie = self.bepinex.clone();
+                self.fetch_promise = Some(poll_promise::Promise::spawn_thread(
+                    "revalidate",
+                    move || {
+                        bie.revalidate();
+                        bie
+                    },
+                ));
+                return;
+            }
+
+            if let Some(req) = &self.version_req {
+                if self.selected_bie.is_none() {
+                    toasts.error(format!("No release matches {}", req), options);
+                }
+            }
+        }
+    }
 
-                self.bepinex.releases = releases;
+    /// Progress bar with a cancel button while downloading, the install button otherwise
+    fn show_install(&mut self, ui: &mut Ui, toasts: &mut Toasts, options: ToastOptions) {
+        if let Some(rx) = &self.dl_progress_rx {
+            if let Some(progress) = rx.try_iter().last() {
+                self.dl_progress = Some(progress);
+            }
+        }
+
+        let finished = self
+            .dl_promise
+            .as_ref()
+            .and_then(|promise| promise.ready())
+            .map(|result| {
+                result
+                    .as_ref()
+                    .map_err(|e| (e.to_string(), e.is::<Cancelled>()))
+            });
+        if let Some(result) = finished {
+            match result {
+                Ok(plan) => toasts.success(
+                    format!(
+                        "Installed, {} files written ({} replaced)",
+                        plan.files.len(),
+                        plan.overwritten().count()
+                    ),
+                    options,
+                ),
+                Err((_, true)) => toasts.info("Download cancelled", options),
+                Err((error, false)) => toasts.error(error, options),
+            };
+            self.dl_promise = None;
+            self.dl_progress_rx = None;
+            self.dl_progress = None;
+        }
+
+        let planned = match self.plan_promise.take() {
+            Some(promise) => match promise.try_take() {
+                Ok(result) => Some(result.map_err(|e| (e.to_string(), e.is::<Cancelled>()))),
+                Err(promise) => {
+                    self.plan_promise = Some(promise);
+                    None
+                }
+            },
+            None => None,
+        };
+        if let Some(result) = planned {
+            match (result, &self.selected_game, &self.selected_bie) {
+                (Ok((plan, archive)), Some(game), Some(release)) => {
+                    let changelog = self.bepinex.changelog(game, release);
+                    self.confirm_install = Some((plan, changelog));
+                    self.planned_archive = Some(archive);
+                }
+                (Ok(_), _, _) => {}
+                (Err((_, true)), _, _) => {
+                    toasts.info("Download cancelled", options);
+                }
+                (Err((error, false)), _, _) => {
+                    toasts.error(error, options);
+                }
+            }
+            self.plan_promise = None;
+            self.dl_progress_rx = None;
+            self.dl_progress = None;
+        }
+
+        if self.dl_promise.is_none() && self.plan_promise.is_none() {
+            ui.centered_and_justified(|ui| {
+                if ui.button("Install").clicked() {
+                    self.request_install(toasts, options);
+                }
             });
+            return;
+        }
+
+        ui.horizontal_centered(|ui| {
+            let cancel_width = 80.0;
+            let bar = match &self.dl_progress {
+                Some(progress) => {
+                    let mut text = format_mib(progress.downloaded);
+                    if let Some(total) = progress.total {
+                        text = format!("{} / {}", text, format_mib(total));
+                    }
+                    if progress.bytes_per_sec > 0.0 {
+                        text =
+                            format!("{} – {}/s", text, format_mib(progress.bytes_per_sec as u64));
+                    }
+                    ProgressBar::new(progress.fraction().unwrap_or_default())
+                        .animate(progress.fraction().is_none())
+                        .text(text)
+                }
+                None => ProgressBar::new(0.0).animate(true).text("Connecting…"),
+            };
+            ui.add(bar.desired_width(ui.available_width() - cancel_width));
+            if ui
+                .add_enabled(!self.dl_cancel.is_cancelled(), Button::new("Cancel"))
+                .clicked()
+            {
+                self.dl_cancel.cancel();
+            }
+        });
+    }
+
+    /// Age of the oldest cached release list, `None` if everything was just fetched
+    fn cache_status(&self) -> Option<String> {
+        let oldest = self
+            .bepinex
+            .statuses()
+            .filter(|status| status.from_cache)
+            .max_by_key(|status| status.age())?;
+
+        let mut text = format!("cached {} ago", format_age(oldest.age()));
+        if self.settings.offline {
+            text.push_str(", offline");
+        } else if self.revalidating {
+            text.push_str(", refreshing…");
         }
+        Some(text)
+    }
+
+    /// Newest release that can be installed into the selected game, or the newest one that
+    /// matches `--version` if it was given
+    fn select_latest(&mut self) {
+        if let Some(req) = &self.version_req {
+            self.selected_bie = self
+                .bepinex
+                .latest_matching(req, self.selected_game.as_ref());
+            if let Some(release) = &self.selected_bie {
+                self.release_flavor = release.flavor.clone();
+            }
+            return;
+        }
+
+        self.selected_bie = match &self.selected_game {
+            Some(game) => self
+                .bepinex
+                .recommended_for(game, self.release_flavor.clone()),
+            None => self.bepinex.latest(),
+        };
     }
 
     fn show_games_select(&mut self, ui: &mut Ui) {
+        let mut changed = false;
         ComboBox::from_id_source("game_selector")
             .width(ui.available_width() - 8.0)
             .selected_text(
@@ -94,79 +328,226 @@ impl Installer {
             )
             .show_ui(ui, |ui| {
                 for game in self.games.iter() {
-                    ui.selectable_value(&mut self.selected_game, Some(game.to_owned()), &game.name);
+                    if ui
+                        .selectable_value(
+                            &mut self.selected_game,
+                            Some(game.to_owned()),
+                            &game.name,
+                        )
+                        .changed()
+                    {
+                        changed = true;
+                    }
                 }
             });
+
+        if changed {
+            self.select_latest();
+        }
     }
 
     fn show_bie_select(&mut self, ui: &mut Ui) {
         ComboBox::from_id_source("bie_selector")
             .width(ui.available_width() - 8.0)
             .selected_text(
-                &self
-                    .selected_bie
+                self.selected_bie
                     .as_ref()
-                    .map(|b| b.to_string())
+                    .map(|b| b.label())
                     .unwrap_or_else(|| "None".to_string()),
             )
             .show_ui(ui, |ui| {
-                for bie_ver in self
-                    .bepinex
-                    .releases
-                    .iter()
+                let releases = match &self.selected_game {
+                    Some(game) => self.bepinex.compatible_releases(game),
+                    None => self.bepinex.releases.iter().collect(),
+                };
+                for bie_ver in releases
+                    .into_iter()
                     .filter(|r| r.flavor == self.release_flavor)
                 {
                     ui.selectable_value(
                         &mut self.selected_bie,
                         Some(bie_ver.to_owned()),
-                        &bie_ver.version.to_string(),
+                        bie_ver.label(),
                     );
                 }
             });
     }
 
     fn release_flavor_select(&mut self, ui: &mut Ui) {
-        ui.radio_value(
+        let stable = ui.radio_value(
             &mut self.release_flavor,
             ReleaseFlavor::Stable,
             ReleaseFlavor::Stable.to_string(),
         );
-        ui.radio_value(
+        let bleeding_edge = ui.radio_value(
             &mut self.release_flavor,
             ReleaseFlavor::BleedingEdge,
             ReleaseFlavor::BleedingEdge.to_string(),
         );
+        if stable.changed() || bleeding_edge.changed() {
+            // Picking a release type by hand overrides `--version`
+            self.version_req = None;
+            self.select_latest();
+        }
     }
 
-    fn install_bie(&mut self, toasts: &mut Toasts, options: ToastOptions) {
-        if let (Some(selected_game), Some(selected_bie)) = (&self.selected_game, &self.selected_bie)
-        {
-            let supported_ver = selected_game.ty == Some(GameType::UnityIL2CPP)
-                && selected_bie.version >= *MIN_IL2CPP_STABLE_VERSION;
+    /// Downloader reporting to the progress bar, with a fresh cancel token
+    fn downloader(&mut self) -> Downloader {
+        let (progress_tx, progress_rx) = mpsc::channel();
+        self.dl_cancel = CancelToken::default();
+        self.dl_progress = None;
+        self.dl_progress_rx = Some(progress_rx);
 
-            let supported = supported_ver || (selected_game.ty != Some(GameType::UnityIL2CPP));
-            if !supported {
-                toasts.error(
-                    format!(
-                        "Minimal BepInEx for this game is {}",
-                        *MIN_IL2CPP_STABLE_VERSION
-                    ),
-                    options,
-                );
-                return;
-            }
+        let mut downloader = Downloader::new(self.client.clone());
+        downloader
+            .set_cache(self.artifacts.clone())
+            .set_progress(Some(progress_tx))
+            .set_cancel_token(self.dl_cancel.clone());
+        downloader
+    }
 
-            let query = selected_bie.to_query(selected_game);
-            if let Some(asset) = selected_bie.select_asset(query) {
-                let game = selected_game.clone();
-                self.dl_promise = Some(poll_promise::Promise::spawn_thread("dl", move || {
-                    asset.download(&game)
-                }));
-            } else {
-                toasts.error("Failed to find asset", options);
-            }
+    /// Plans the install in the background, the plan is shown for confirmation once the
+    /// archive is downloaded
+    fn request_install(&mut self, toasts: &mut Toasts, options: ToastOptions) {
+        let (game, release) = match (&self.selected_game, &self.selected_bie) {
+            (Some(game), Some(release)) => (game.clone(), release.clone()),
+            _ => return,
+        };
+
+        let rules = &self.bepinex.rules;
+        if let Some(bad) = rules.known_bad(&release) {
+            toasts.error(
+                format!("BepInEx {} is known to be broken: {}", release, bad.reason),
+                options,
+            );
+            return;
+        }
+        if !rules.supports_runtime(&release, &game) {
+            match rules.min_stable_for(&game) {
+                Some(min) => {
+                    toasts.error(format!("Minimal BepInEx for this game is {}", min), options)
+                }
+                None => toasts.error("This BepInEx doesn't support the game", options),
+            };
+            return;
+        }
+
+        let downloader = self.downloader();
+        let bepinex = self.bepinex.clone();
+        self.plan_promise = Some(poll_promise::Promise::spawn_thread("plan", move || {
+            bepinex.plan_install_with_archive(&game, &release, &downloader)
+        }));
+    }
+
+    /// Extracts the archive downloaded for the confirmed plan and removes the files it
+    /// listed as left over
+    fn install_bie(&mut self, plan: InstallPlan) {
+        let archive = self.planned_archive.take();
+        if let Some(game) = self.selected_game.clone() {
+            let downloader = self.downloader();
+            self.dl_promise = Some(poll_promise::Promise::spawn_thread(
+                "dl",
+                move || {
+                    let installed = match archive {
+                        Some(archive) => archive.extract(&game)?,
+                        None => plan.asset.download(&game, &downloader)?,
+                    };
+                    // The user saw these in the plan and confirmed it
+                    plan.prune_stale()?;
+                    Ok(installed)
+                },
+            ));
         }
     }
+
+    fn show_install_confirm(&mut self, ctx: &Context) {
+        let (plan, changelog) = match &self.confirm_install {
+            Some(confirm) => confirm.clone(),
+            None => return,
+        };
+
+        Window::new(format!("Install BepInEx {}", plan.version.display()))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} ({}{})",
+                    plan.asset.name,
+                    format_mib(plan.download_size),
+                    if plan.cached { ", cached" } else { "" }
+                ));
+                ui.separator();
+
+                if !plan.warnings.is_empty() {
+                    ui.label("Warnings:");
+                    for warning in plan.warnings.iter() {
+                        ui.label(format!("• {}", warning));
+                    }
+                    ui.separator();
+                }
+
+                ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for (title, paths) in [
+                        ("Add", &plan.add),
+                        ("Overwrite", &plan.overwrite),
+                        ("Remove", &plan.remove),
+                        ("Keep", &plan.preserve),
+                    ] {
+                        if paths.is_empty() {
+                            continue;
+                        }
+                        CollapsingHeader::new(format!("{} {} files", title, paths.len())).show(
+                            ui,
+                            |ui| {
+                                for path in paths.iter() {
+                                    ui.monospace(path.display().to_string());
+                                }
+                            },
+                        );
+                    }
+
+                    if !changelog.is_empty() {
+                        ui.separator();
+                        ui.label("Changes:");
+                        for entry in changelog.entries.iter() {
+                            CollapsingHeader::new(entry.label())
+                                .default_open(changelog.entries.len() == 1)
+                                .show(ui, |ui| {
+                                    if let Some(notes) = &entry.notes {
+                                        ui.label(notes);
+                                    }
+                                    for commit in entry.commits.iter() {
+                                        ui.horizontal(|ui| {
+                                            ui.monospace(&commit.hash);
+                                            ui.label(commit.subject());
+                                        });
+                                    }
+                                    if let Some(url) = &entry.url {
+                                        ui.hyperlink_to("Release page", url);
+                                    }
+                                });
+                        }
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let install_text = match plan.warnings.is_empty() {
+                        true => "Install",
+                        false => "Install anyway",
+                    };
+                    if ui.button(install_text).clicked() {
+                        self.confirm_install = None;
+                        self.install_bie(plan.clone());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_install = None;
+                        self.planned_archive = None;
+                    }
+                });
+            });
+    }
 }
 
 impl App for Installer {
@@ -175,6 +556,10 @@ impl App for Installer {
             .anchor((10., 10.))
             .direction(Direction::TopDown)
             .align_to_end(false);
+        let options = ToastOptions {
+            show_icon: true,
+            ..ToastOptions::with_duration(Duration::from_secs(2))
+        };
         CentralPanel::default().show(ctx, |ui| {
             StripBuilder::new(ui)
                 .size(Size::exact(30.0))
@@ -219,7 +604,7 @@ impl App for Installer {
                             .size(Size::remainder())
                             .size(Size::exact(40.0))
                             .vertical(|mut strip| {
-                                if let (Some(selected_game), Some(_selected_bie)) =
+                                if let (Some(selected_game), Some(selected_bie)) =
                                     (&self.selected_game, &self.selected_bie)
                                 {
                                     strip.cell(|ui| {
@@ -237,6 +622,11 @@ impl App for Installer {
                                                 }
                                             });
                                             ui.separator();
+                                            ui.horizontal(|ui| {
+                                                ui.label("Runtime:");
+                                                ui.monospace(selected_game.runtime.to_string());
+                                            });
+                                            ui.separator();
                                             ui.horizontal(|ui| {
                                                 ui.label("Installed BepInEx:");
                                                 match &selected_game.bepinex_version {
@@ -244,34 +634,47 @@ impl App for Installer {
                                                     None => ui.monospace("None"),
                                                 }
                                             });
-                                        });
-                                    });
-                                    strip.cell(|ui| {
-                                        ui.centered_and_justified(|ui| {
-                                            let options = ToastOptions {
-                                                show_icon: true,
-                                                ..ToastOptions::with_duration(Duration::from_secs(
-                                                    2,
-                                                ))
-                                            };
-                                            if ui.button("Install").clicked() {
-                                                self.shown_toast = false;
-                                                self.install_bie(&mut toasts, options);
+                                            if let Some(status) = self.cache_status() {
+                                                ui.separator();
+                                                ui.horizontal(|ui| {
+                                                    ui.label("Releases:");
+                                                    ui.monospace(status);
+                                                });
                                             }
-                                            if let Some(dl_promise) = &self.dl_promise {
-                                                if let Some(r) = dl_promise.ready() {
-                                                    if let Err(e) = r {
-                                                        toasts.error(e.to_string(), options);
-                                                    } else {
-                                                        toasts.success("Installed.", options);
+                                            let runtime_warning = self
+                                                .bepinex
+                                                .rules
+                                                .runtime_warning(selected_bie, selected_game);
+                                            if !selected_game.warnings.is_empty()
+                                                || runtime_warning.is_some()
+                                            {
+                                                ui.separator();
+                                                ui.label("Warnings:");
+                                                for warning in selected_game.warnings.iter() {
+                                                    ui.monospace(warning.to_string());
+                                                }
+                                                if let Some(warning) = runtime_warning {
+                                                    ui.monospace(warning);
+                                                }
+                                            }
+                                            ui.separator();
+                                            if ui.button("Copy Player.log").clicked() {
+                                                match selected_game.tail_player_log(200) {
+                                                    Some((path, log)) => {
+                                                        ui.output().copied_text =
+                                                            format!("{}\n{}", path.display(), log);
+                                                        toasts.info("Copied Player.log", options);
+                                                    }
+                                                    None => {
+                                                        toasts
+                                                            .error("Player.log not found", options);
                                                     }
-                                                    self.dl_promise = None;
-                                                } else if !self.shown_toast {
-                                                    toasts.info("Downloading...", options);
-                                                    self.shown_toast = true;
                                                 }
                                             }
                                         });
+                                    });
+                                    strip.cell(|ui| {
+                                        self.show_install(ui, &mut toasts, options);
                                     })
                                 }
                             });
@@ -279,11 +682,18 @@ impl App for Installer {
                 });
         });
 
-        if self.bepinex.releases.is_empty() {
-            self.fetch()
-        };
+        if self.confirm_install.is_some() {
+            self.show_install_confirm(ctx);
+        }
 
-        ctx.request_repaint_after(Duration::from_secs(1));
+        if self.fetch_promise.is_some() {
+            self.fetch(&mut toasts, options);
+        }
+
+        match self.dl_promise.is_some() || self.plan_promise.is_some() {
+            true => ctx.request_repaint_after(Duration::from_millis(100)),
+            false => ctx.request_repaint_after(Duration::from_secs(1)),
+        }
         toasts.show(ctx);
     }
 }
@@ -1,19 +1,173 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 pub mod installer;
+pub mod settings;
 
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+
+use bepinex_helpers::game::Game;
+use bepinex_sources::{
+    bepinex::ReleaseFlavor,
+    cache::{ArtifactCache, ReleaseCache},
+    client::SourcesClient,
+    download::Downloader,
+    version::ReleaseRequirement,
+};
 use eframe::{egui, run_native, NativeOptions};
-use lazy_static::lazy_static;
-use semver::Version;
 
-use crate::installer::Installer;
+use crate::{installer::Installer, settings::Settings};
+
+#[derive(Default)]
+struct Args {
+    /// `--version <req>`, release to preselect
+    version_req: Option<ReleaseRequirement>,
+    /// `--plan <game dir>`, prints the install plan instead of opening the window
+    plan: Option<PathBuf>,
+    /// `--json`, prints the plan as JSON
+    json: bool,
+    clear_cache: bool,
+}
+
+/// Release builds use the GUI subsystem, so Windows doesn't give them a console and
+/// everything the CLI modes print is lost. This attaches to the console of the shell the
+/// installer was started from, unless the output is already redirected to a file or pipe.
+#[cfg(windows)]
+fn attach_console() {
+    use std::ffi::c_void;
+
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+    }
+
+    // Fails when there's no parent console, e.g. started from Explorer, nothing to do then
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Reads `--name value` and `--name=value` arguments
+fn parse_args() -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_owned())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = |hint: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(format!("{} needs {}", name, hint))
+        };
+
+        match name {
+            "--version" => {
+                let value = value("a requirement, e.g. ^5.4")?;
+                let req = value
+                    .parse()
+                    .map_err(|e| format!("Invalid version requirement {}: {}", value, e))?;
+                parsed.version_req = Some(req);
+            }
+            "--plan" => parsed.plan = Some(PathBuf::from(value("a game folder")?)),
+            "--json" => parsed.json = true,
+            "--clear-cache" => parsed.clear_cache = true,
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// `--clear-cache`, removes cached release lists and downloads
+fn clear_cache() -> anyhow::Result<u64> {
+    let dir = Settings::cache_dir().ok_or_else(|| anyhow::anyhow!("No cache directory"))?;
+    let releases = ReleaseCache::new(dir.join("releases")).clear()?;
+    let artifacts = ArtifactCache::new(dir.join("artifacts")).clear()?;
+    Ok(releases + artifacts)
+}
+
+/// `--plan <game dir>`, prints what installing into the game would do without doing it
+fn print_plan(game_dir: &Path, req: Option<&ReleaseRequirement>, json: bool) -> anyhow::Result<()> {
+    let name = game_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let game = Game::from_path(name, game_dir)
+        .ok_or_else(|| anyhow::anyhow!("{} isn't a Unity game", game_dir.display()))?;
+
+    let settings = Settings::load()?;
+    let client = SourcesClient::new(settings.network.clone())?;
+    let mut bie = settings.bepinex(Settings::load_rules()?, &client);
+    for error in bie.fetch().errors.iter() {
+        eprintln!("Failed to fetch releases from {}", error);
+    }
+    for error in bie.revalidate().errors.iter() {
+        eprintln!("Failed to fetch releases from {}", error);
+    }
+
+    let release = match req {
+        Some(req) => bie.latest_matching(req, Some(&game)),
+        None => bie.recommended_for(&game, ReleaseFlavor::Stable),
+    }
+    .ok_or_else(|| anyhow::anyhow!("No release for {} found", game.name))?;
 
-lazy_static! {
-    pub static ref MIN_SUPPORTED_STABLE_VERSION: Version = Version::parse("5.4.11").unwrap();
-    pub static ref MIN_IL2CPP_STABLE_VERSION: Version = Version::parse("6.0.0-pre.1").unwrap();
+    let mut downloader = Downloader::new(client);
+    downloader.set_cache(settings.artifact_cache());
+    let plan = bie.plan_install(&game, &release, &downloader)?;
+    match json {
+        true => println!("{}", plan.to_json()?),
+        false => print!("{}", plan),
+    }
+    Ok(())
 }
 
 fn main() {
+    if env::args().len() > 1 {
+        attach_console();
+    }
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.clear_cache {
+        match clear_cache() {
+            Ok(freed) => println!("Cleared {:.2} MiB", freed as f64 / 1024.0 / 1024.0),
+            Err(e) => {
+                eprintln!("Failed to clear the cache: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(game_dir) = &args.plan {
+        if let Err(e) = print_plan(game_dir, args.version_req.as_ref(), args.json) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let version_req = args.version_req;
     let min_size = Some(egui::vec2(400.0, 450.0));
     let options = NativeOptions {
         follow_system_theme: true,
@@ -26,6 +180,6 @@ fn main() {
     run_native(
         "BepInEx Installer",
         options,
-        Box::new(|_cc| Box::new(Installer::new())),
+        Box::new(move |_cc| Box::new(Installer::new(version_req))),
     )
 }
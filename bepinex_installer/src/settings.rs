@@ -0,0 +1,127 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use bepinex_sources::{
+    bepinex::BepInEx,
+    builds::BuildsApi,
+    cache::{ArtifactCache, ReleaseCache},
+    client::{ClientConfig, SourcesClient},
+    github::GitHubApi,
+    local::LocalSource,
+    mirror::MirrorSource,
+    rules::Rules,
+};
+use serde::{Deserialize, Serialize};
+
+/// User settings, read from `<config dir>/bepinex-installer/settings.toml`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Steam installs that aren't found automatically, e.g. on another drive
+    pub steam_roots: Vec<PathBuf>,
+    /// Folders with BepInEx zips to offer next to the online releases
+    pub local_sources: Vec<PathBuf>,
+    /// URLs of self-hosted mirror `index.json` files
+    pub mirrors: Vec<String>,
+    /// Raises the GitHub API rate limit, `GITHUB_TOKEN` is used when unset
+    pub github_token: Option<String>,
+    /// Timeouts, proxy and certificates for every request, the `[network]` table
+    pub network: ClientConfig,
+    /// Only use cached releases, never query the sources
+    pub offline: bool,
+    /// How long fetched releases are reused before querying the sources again, an hour if
+    /// unset
+    pub cache_ttl_secs: Option<u64>,
+    /// Downloaded archives are evicted above this, 2 GiB if unset
+    pub max_cache_size_mib: Option<u64>,
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("bepinex-installer").join("settings.toml"))
+    }
+
+    /// Local override of the bundled compatibility rules
+    pub fn rules_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("bepinex-installer").join("rules.json"))
+    }
+
+    /// Release lists and downloads are cached in here
+    pub fn cache_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("bepinex-installer"))
+    }
+
+    /// The local rules override if there is one, the bundled rules otherwise
+    pub fn load_rules() -> anyhow::Result<Rules> {
+        match Self::rules_path() {
+            Some(path) => Rules::load(&path),
+            None => Ok(Rules::bundled()),
+        }
+    }
+
+    pub fn release_cache(&self) -> Option<ReleaseCache> {
+        Self::cache_dir().map(|dir| {
+            let mut cache = ReleaseCache::new(dir.join("releases"));
+            cache.set_offline(self.offline);
+            if let Some(ttl) = self.cache_ttl_secs {
+                cache.set_ttl(Duration::from_secs(ttl));
+            }
+            cache
+        })
+    }
+
+    pub fn artifact_cache(&self) -> Option<ArtifactCache> {
+        Self::cache_dir().map(|dir| {
+            let mut artifacts = ArtifactCache::new(dir.join("artifacts"));
+            if let Some(max_size) = self.max_cache_size_mib {
+                artifacts.set_max_size(max_size * 1024 * 1024);
+            }
+            artifacts
+        })
+    }
+
+    /// GitHub, builds.bepinex.dev, local folders and mirrors, ready to be fetched
+    pub fn bepinex(&self, rules: Rules, client: &SourcesClient) -> BepInEx {
+        let mut gh = GitHubApi::new("BepInEx", "BepInEx");
+        gh.set_pre_releases(true)
+            .set_min_tag(Some(rules.min_stable_version.clone()))
+            .set_client(client.clone());
+        if self.github_token.is_some() {
+            gh.set_token(self.github_token.clone());
+        }
+        let mut be = BuildsApi::new(client.builds_url());
+        be.set_client(client.clone());
+
+        let mut bie = BepInEx::default();
+        bie.set_rules(rules)
+            .set_cache(self.release_cache())
+            .register(gh)
+            .register(be);
+        for dir in self.local_sources.iter() {
+            bie.register(LocalSource::new(dir));
+        }
+        for index_url in self.mirrors.iter() {
+            let mut mirror = MirrorSource::new(index_url);
+            mirror.set_client(client.clone());
+            bie.register(mirror);
+        }
+        bie
+    }
+
+    /// Defaults if there's no settings file yet. A file that can't be read or parsed is an
+    /// error, so a typo doesn't silently drop the rest of the settings.
+    pub fn load() -> anyhow::Result<Self> {
+        match Self::path().filter(|path| path.exists()) {
+            Some(path) => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow::anyhow!("No config directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use bepinex_sources::{bepinex::BepInEx, builds::BuildsApi, github::GitHubApi};
+use semver::Version;
+
+/// `cargo run --example async_sources --features async`
+#[tokio::main]
+async fn main() {
+    let mut gh = GitHubApi::new("BepInEx", "BepInEx");
+    gh.set_min_tag(Some(Version::parse("5.4.11").unwrap()));
+    let be = BuildsApi::new("https://builds.bepinex.dev");
+
+    let mut bie = BepInEx::default();
+    bie.register(gh).register(be).fetch_async().await;
+
+    for release in bie.releases.iter() {
+        println!("[{}] {} ({})", release.source, release, release.flavor);
+    }
+    for error in bie.errors.iter() {
+        eprintln!("{error}");
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use bepinex_sources::{
+    bepinex::BepInEx, builds::BuildsApi, client::SourcesClient, github::GitHubApi,
+    mirror::MirrorIndex,
+};
+use semver::Version;
+
+/// Downloads every release since 5.4.21 and the latest bleeding edge builds into `./mirror`
+/// along with an `index.json` that `MirrorSource` can consume
+fn main() -> anyhow::Result<()> {
+    let mut gh = GitHubApi::new("BepInEx", "BepInEx");
+    gh.set_min_tag(Some(Version::parse("5.4.21").unwrap()));
+    let mut be = BuildsApi::new("https://builds.bepinex.dev");
+    be.set_min_build_id(Some(670));
+
+    let mut bie = BepInEx::default();
+    bie.register(gh).register(be).fetch();
+    if let Some(error) = bie.errors.first() {
+        anyhow::bail!("{error}");
+    }
+
+    let dir = Path::new("mirror");
+    let mut index = MirrorIndex::from_releases(&bie.releases);
+    index.download_to(dir, &SourcesClient::default())?;
+    index.write(&dir.join("index.json"))?;
+
+    println!("Mirrored {} releases", index.releases.len());
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::env;
+
+use bepinex_sources::{
+    bepinex::BepInEx, builds::BuildsApi, github::GitHubApi, version::ReleaseRequirement,
+};
+use semver::Version;
+
+/// `cargo run --example sources -- --version ">=6.0.0-be.650, <6.0.0-be.700"`
+fn main() -> anyhow::Result<()> {
+    let req = match env::args().skip_while(|arg| arg != "--version").nth(1) {
+        Some(req) => Some(req.parse::<ReleaseRequirement>()?),
+        None => None,
+    };
+
+    let mut gh = GitHubApi::new("BepInEx", "BepInEx");
+    gh.set_min_tag(Some(Version::parse("5.4.11").unwrap()));
+    let be = BuildsApi::new("https://builds.bepinex.dev");
+
+    let mut bie = BepInEx::default();
+    bie.register(gh).register(be).fetch();
+
+    match &req {
+        Some(req) => match bie.latest_matching(req, None) {
+            Some(release) => println!("[{}] {} ({})", release.source, release, release.flavor),
+            None => eprintln!("No release matches {req}"),
+        },
+        None => {
+            for release in bie.releases.iter() {
+                println!("[{}] {} ({})", release.source, release, release.flavor);
+            }
+        }
+    }
+    for error in bie.errors.iter() {
+        eprintln!("{error}");
+    }
+    Ok(())
+}
@@ -0,0 +1,450 @@
+use std::fmt::Display;
+
+use bepinex_helpers::game::{Game, GameType};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::{BuildMetadata, Version};
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    static ref NAME_VERSION_REGEX: Regex =
+        Regex::new(r"(\d+)\.(\d+)\.(\d+)(?:\.(\d+))?(?:-([0-9A-Za-z.]+))?(?:\+([0-9A-Za-z.]+))?$")
+            .unwrap();
+    static ref HASH_REGEX: Regex = Regex::new(r"^[0-9a-f]{7,40}$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssetRuntime {
+    #[serde(rename = "unity_mono")]
+    UnityMono,
+    #[serde(rename = "unity_il2cpp")]
+    UnityIL2CPP,
+    #[serde(rename = "net_framework")]
+    NetFramework,
+    #[serde(rename = "net_coreclr")]
+    NetCoreCLR,
+}
+
+impl From<&GameType> for AssetRuntime {
+    fn from(ty: &GameType) -> Self {
+        match ty {
+            GameType::UnityMono => AssetRuntime::UnityMono,
+            GameType::UnityIL2CPP => AssetRuntime::UnityIL2CPP,
+        }
+    }
+}
+
+impl Display for AssetRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetRuntime::UnityMono => write!(f, "Unity.Mono"),
+            AssetRuntime::UnityIL2CPP => write!(f, "Unity.IL2CPP"),
+            AssetRuntime::NetFramework => write!(f, "NET.Framework"),
+            AssetRuntime::NetCoreCLR => write!(f, "NET.CoreCLR"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetPlatform {
+    Windows,
+    Linux,
+    MacOS,
+    /// Linux and macOS in one archive, used by BepInEx 5 and 6 pre-releases
+    Unix,
+}
+
+impl Display for AssetPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetPlatform::Windows => write!(f, "win"),
+            AssetPlatform::Linux => write!(f, "linux"),
+            AssetPlatform::MacOS => write!(f, "macos"),
+            AssetPlatform::Unix => write!(f, "unix"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetArch {
+    X64,
+    X86,
+    Arm64,
+}
+
+impl Display for AssetArch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetArch::X64 => write!(f, "x64"),
+            AssetArch::X86 => write!(f, "x86"),
+            AssetArch::Arm64 => write!(f, "arm64"),
+        }
+    }
+}
+
+/// Everything that can be told about a BepInEx archive from its file name. Handles every
+/// naming scheme used so far:
+///
+/// - `BepInEx_x64_5.4.21.0.zip`, `BepInEx_unix_5.4.21.0.zip`
+/// - `BepInEx_UnityIL2CPP_x86_6.0.0-pre.1.zip`
+/// - `BepInEx_UnityMono_x64_3a54f7e_6.0.0-be.571.zip`
+/// - `BepInEx-Unity.IL2CPP-win-x64-6.0.0-be.674+82077ec.zip`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetDescriptor {
+    /// `None` when the name has an unknown runtime, BepInEx 5 archives are always Unity.Mono
+    pub runtime: Option<AssetRuntime>,
+    pub platform: Option<AssetPlatform>,
+    pub arch: Option<AssetArch>,
+    pub version: Version,
+    /// Bleeding edge artifact number
+    pub build: Option<u32>,
+    /// Commit the archive was built from
+    pub hash: Option<String>,
+}
+
+impl AssetDescriptor {
+    pub fn parse(name: &str) -> Option<Self> {
+        // Names can end in any character, only slice once the suffix is known to be ASCII
+        let is_zip = name
+            .get(name.len().saturating_sub(4)..)
+            .is_some_and(|ext| name.len() > 4 && ext.eq_ignore_ascii_case(".zip"));
+        let stem = match is_zip {
+            true => &name[..name.len() - 4],
+            false => name,
+        };
+        let stem = stem.strip_prefix("BepInEx")?;
+
+        let captures = NAME_VERSION_REGEX.captures(stem)?;
+        let mut version = Version::parse(&format!(
+            "{}.{}.{}{}",
+            &captures[1],
+            &captures[2],
+            &captures[3],
+            captures
+                .get(5)
+                .map(|pre| format!("-{}", pre.as_str()))
+                .unwrap_or_default()
+        ))
+        .ok()?;
+
+        let mut descriptor = AssetDescriptor {
+            runtime: None,
+            platform: None,
+            arch: None,
+            version: Version::new(0, 0, 0),
+            build: None,
+            hash: captures.get(6).map(|hash| hash.as_str().to_owned()),
+        };
+
+        let prefix = &stem[..captures.get(0)?.start()];
+        for token in prefix.split(['_', '-']).filter(|t| !t.is_empty()) {
+            match token.to_ascii_lowercase().as_str() {
+                "unitymono" | "unity.mono" => descriptor.runtime = Some(AssetRuntime::UnityMono),
+                "unityil2cpp" | "unity.il2cpp" => {
+                    descriptor.runtime = Some(AssetRuntime::UnityIL2CPP)
+                }
+                "netlauncher" | "net.framework" => {
+                    descriptor.runtime = Some(AssetRuntime::NetFramework)
+                }
+                "net.coreclr" => descriptor.runtime = Some(AssetRuntime::NetCoreCLR),
+                "win" => descriptor.platform = Some(AssetPlatform::Windows),
+                "linux" => descriptor.platform = Some(AssetPlatform::Linux),
+                "macos" | "osx" => descriptor.platform = Some(AssetPlatform::MacOS),
+                "unix" => descriptor.platform = Some(AssetPlatform::Unix),
+                "x64" => descriptor.arch = Some(AssetArch::X64),
+                "x86" => descriptor.arch = Some(AssetArch::X86),
+                "arm64" => descriptor.arch = Some(AssetArch::Arm64),
+                hash if HASH_REGEX.is_match(hash) => descriptor.hash = Some(hash.to_owned()),
+                _ => {}
+            }
+        }
+
+        // Names without a platform are Windows builds, unless they're the unix archive
+        if descriptor.platform.is_none() && descriptor.arch.is_some() {
+            descriptor.platform = Some(AssetPlatform::Windows);
+        }
+        if descriptor.runtime.is_none() && version.major == 5 {
+            descriptor.runtime = Some(AssetRuntime::UnityMono);
+        }
+
+        descriptor.build = version
+            .pre
+            .strip_prefix("be.")
+            .and_then(|build| build.parse().ok());
+
+        // Keep versions comparable with the ones reported by the sources: builds.bepinex.dev
+        // appends the commit hash, 5.x revisions like 5.4.23.2 are kept as build metadata
+        let build_metadata = descriptor
+            .hash
+            .as_deref()
+            .filter(|_| descriptor.build.is_some())
+            .or_else(|| captures.get(4).map(|r| r.as_str()).filter(|r| *r != "0"));
+        if let Some(build_metadata) = build_metadata {
+            version.build = BuildMetadata::new(build_metadata).ok()?;
+        }
+        descriptor.version = version;
+
+        Some(descriptor)
+    }
+
+    /// Name in the current `BepInEx-Unity.Mono-win-x64-<version>.zip` scheme, which
+    /// [`parse`](AssetDescriptor::parse) reads back
+    pub fn file_name(&self) -> String {
+        let mut parts = vec!["BepInEx".to_owned()];
+        parts.extend(self.runtime.map(|runtime| runtime.to_string()));
+        parts.extend(self.platform.map(|platform| platform.to_string()));
+        parts.extend(self.arch.map(|arch| arch.to_string()));
+        // 5.x revisions are kept as build metadata, but named as a fourth number
+        let version = &self.version;
+        parts.push(match self.build.is_none() && !version.build.is_empty() {
+            true => {
+                let pre = Some(version.pre.as_str()).filter(|pre| !pre.is_empty());
+                format!(
+                    "{}.{}.{}.{}{}",
+                    version.major,
+                    version.minor,
+                    version.patch,
+                    version.build,
+                    pre.map(|pre| format!("-{}", pre)).unwrap_or_default()
+                )
+            }
+            false => version.to_string(),
+        });
+        format!("{}.zip", parts.join("-"))
+    }
+
+    pub fn matches(&self, game: &Game) -> bool {
+        let runtime = match &game.ty {
+            Some(ty) => AssetRuntime::from(ty),
+            None => return false,
+        };
+
+        // Games are found through UnityPlayer.dll, so they're Windows builds even under Proton
+        self.runtime == Some(runtime)
+            && self.platform == Some(AssetPlatform::Windows)
+            && self
+                .arch
+                .map(|arch| arch.to_string() == game.arch)
+                .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use AssetArch::*;
+    use AssetPlatform::*;
+    use AssetRuntime::*;
+
+    type Expected = (
+        Option<AssetRuntime>,
+        Option<AssetPlatform>,
+        Option<AssetArch>,
+        &'static str,
+        Option<u32>,
+        Option<&'static str>,
+    );
+
+    /// Names from the GitHub and builds fixtures, plus the older schemes
+    const NAMES: &[(&str, Option<Expected>)] = &[
+        (
+            "BepInEx_x64_5.4.21.0.zip",
+            Some((
+                Some(UnityMono),
+                Some(Windows),
+                Some(X64),
+                "5.4.21",
+                None,
+                None,
+            )),
+        ),
+        (
+            "BepInEx_x86_5.4.22.0.zip",
+            Some((
+                Some(UnityMono),
+                Some(Windows),
+                Some(X86),
+                "5.4.22",
+                None,
+                None,
+            )),
+        ),
+        (
+            "BepInEx_x64_5.4.23.2.zip",
+            Some((
+                Some(UnityMono),
+                Some(Windows),
+                Some(X64),
+                "5.4.23+2",
+                None,
+                None,
+            )),
+        ),
+        (
+            "BepInEx_unix_5.4.21.0.zip",
+            Some((Some(UnityMono), Some(Unix), None, "5.4.21", None, None)),
+        ),
+        (
+            "BepInEx_UnityIL2CPP_x86_6.0.0-pre.1.zip",
+            Some((
+                Some(UnityIL2CPP),
+                Some(Windows),
+                Some(X86),
+                "6.0.0-pre.1",
+                None,
+                None,
+            )),
+        ),
+        (
+            "BepInEx_UnityIL2CPP_x64_6.0.0-be.674+82077ec.zip",
+            Some((
+                Some(UnityIL2CPP),
+                Some(Windows),
+                Some(X64),
+                "6.0.0-be.674+82077ec",
+                Some(674),
+                Some("82077ec"),
+            )),
+        ),
+        (
+            "BepInEx_UnityMono_x64_3a54f7e_6.0.0-be.571.zip",
+            Some((
+                Some(UnityMono),
+                Some(Windows),
+                Some(X64),
+                "6.0.0-be.571+3a54f7e",
+                Some(571),
+                Some("3a54f7e"),
+            )),
+        ),
+        (
+            "BepInEx-Unity.Mono-win-x64-6.0.0-be.674+82077ec.zip",
+            Some((
+                Some(UnityMono),
+                Some(Windows),
+                Some(X64),
+                "6.0.0-be.674+82077ec",
+                Some(674),
+                Some("82077ec"),
+            )),
+        ),
+        (
+            "BepInEx-NET.CoreCLR-win-x64-6.0.0-be.673+0d06996.zip",
+            Some((
+                Some(NetCoreCLR),
+                Some(Windows),
+                Some(X64),
+                "6.0.0-be.673+0d06996",
+                Some(673),
+                Some("0d06996"),
+            )),
+        ),
+        (
+            "BepInEx-Unity.IL2CPP-linux-x86-6.0.0-be.674+82077ec",
+            Some((
+                Some(UnityIL2CPP),
+                Some(Linux),
+                Some(X86),
+                "6.0.0-be.674+82077ec",
+                Some(674),
+                Some("82077ec"),
+            )),
+        ),
+        // Unknown runtime tokens are ignored, BepInEx 6 doesn't default to Unity.Mono
+        (
+            "BepInEx_Unreal_x64_6.0.0-pre.1.zip",
+            Some((None, Some(Windows), Some(X64), "6.0.0-pre.1", None, None)),
+        ),
+        ("BepInEx.zip", None),
+        ("BepInEx_x64.zip", None),
+        ("BepInEx_x64_5.4.zip", None),
+        ("BepInEx_x64_5.4.21.0.zip.sha256", None),
+        ("Doorstop_x64_5.4.21.0.zip", None),
+        ("MelonLoader.x64.zip", None),
+        ("BepInEx_é€", None),
+        ("BepInEx_x64_5.4.21.0€.zip", None),
+    ];
+
+    #[test]
+    fn parses_asset_names() {
+        for (name, expected) in NAMES {
+            let parsed = AssetDescriptor::parse(name);
+            let expected =
+                expected.map(
+                    |(runtime, platform, arch, version, build, hash)| AssetDescriptor {
+                        runtime,
+                        platform,
+                        arch,
+                        version: Version::parse(version).unwrap(),
+                        build,
+                        hash: hash.map(str::to_owned),
+                    },
+                );
+            assert_eq!(parsed, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn file_names_parse_back() {
+        for (name, _) in NAMES {
+            if let Some(descriptor) = AssetDescriptor::parse(name) {
+                let parsed = AssetDescriptor::parse(&descriptor.file_name());
+                assert_eq!(parsed, Some(descriptor), "{name}");
+            }
+        }
+    }
+
+    fn game(ty: Option<GameType>, arch: &str) -> Game {
+        Game {
+            arch: arch.to_owned(),
+            ty,
+            ..Game::default()
+        }
+    }
+
+    #[test]
+    fn matches_games() {
+        let mono_x64 = game(Some(GameType::UnityMono), "x64");
+        let il2cpp_x86 = game(Some(GameType::UnityIL2CPP), "x86");
+        let cases = [
+            ("BepInEx_x64_5.4.21.0.zip", &mono_x64, true),
+            (
+                "BepInEx-Unity.Mono-win-x64-6.0.0-be.674+82077ec.zip",
+                &mono_x64,
+                true,
+            ),
+            ("BepInEx_UnityIL2CPP_x86_6.0.0-pre.1.zip", &il2cpp_x86, true),
+            // Wrong arch, runtime or platform
+            ("BepInEx_x86_5.4.22.0.zip", &mono_x64, false),
+            (
+                "BepInEx_UnityIL2CPP_x64_6.0.0-be.674+82077ec.zip",
+                &mono_x64,
+                false,
+            ),
+            ("BepInEx_x86_5.4.22.0.zip", &il2cpp_x86, false),
+            ("BepInEx_unix_5.4.21.0.zip", &mono_x64, false),
+            (
+                "BepInEx-Unity.Mono-linux-x64-6.0.0-be.674+82077ec.zip",
+                &mono_x64,
+                false,
+            ),
+            (
+                "BepInEx-NET.CoreCLR-win-x64-6.0.0-be.673+0d06996.zip",
+                &mono_x64,
+                false,
+            ),
+            ("BepInEx_x64_5.4.21.0.zip", &game(None, "x64"), false),
+        ];
+
+        for (name, game, expected) in cases {
+            let descriptor = AssetDescriptor::parse(name).unwrap();
+            assert_eq!(
+                descriptor.matches(game),
+                expected,
+                "{name} for {:?}",
+                game.ty
+            );
+        }
+    }
+}
@@ -1,33 +1,341 @@
-use std::{fmt::Display, io::Cursor};
+use std::{
+    cmp::Ordering,
+    error,
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
 
-use bepinex_helpers::{game::Game, game_type};
+use bepinex_helpers::game::Game;
+#[cfg(feature = "async")]
+use futures_util::future::join_all;
+use reqwest::Url;
 use semver::Version;
-use zip::ZipArchive;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::{bleeding_edge::builds::BuildsRelease, github::releases::GitHubRelease},
-    version::VersionExt,
+    asset::AssetDescriptor,
+    builds,
+    cache::ReleaseCache,
+    client::SourcesClient,
+    download::Downloader,
+    extract::ExtractionPlan,
+    github,
+    hash::sha256_hex,
+    models::{
+        bleeding_edge::builds::{BuildsCommit, BuildsRelease},
+        github::releases::GitHubRelease,
+    },
+    rules::Rules,
+    source::{ReleaseSource, SourceError, SourceReleases},
+    transport::{block_on, Io},
+    version::cmp_precedence,
 };
 
 #[derive(Debug, Default, Clone)]
 pub struct BepInEx {
     pub releases: Vec<BepInExRelease>,
+    /// Errors of the last time each source was queried, including entries it had to skip.
+    /// [`revalidate`](BepInEx::revalidate) only replaces those of the sources it queries.
+    pub errors: Vec<SourceError>,
+    /// Used to tell which releases can be installed into a game
+    pub rules: Rules,
+    sources: Vec<Arc<dyn ReleaseSource>>,
+    cache: Option<ReleaseCache>,
+    /// Releases of each source, in registration order. `None` if the source failed.
+    fetched: Vec<Option<FetchedReleases>>,
+    /// Why each source failed the last time it was queried, in registration order
+    failures: Vec<Option<SourceError>>,
+}
+
+/// Where the releases of a source came from
+#[derive(Debug, Clone)]
+pub struct SourceStatus {
+    pub source_id: String,
+    /// When the releases were fetched from the source itself
+    pub fetched_at: SystemTime,
+    /// Read from the [`ReleaseCache`] instead of the source
+    pub from_cache: bool,
+    /// Cached for longer than the cache TTL
+    pub stale: bool,
+}
+
+impl SourceStatus {
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.fetched_at)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FetchedReleases {
+    releases: Vec<BepInExRelease>,
+    status: SourceStatus,
+    /// Entries the source skipped, reported in [`BepInEx::errors`]
+    skipped: Vec<SourceError>,
 }
 
 impl BepInEx {
+    pub fn register<S: ReleaseSource + 'static>(&mut self, source: S) -> &mut Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) -> &mut Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn set_cache(&mut self, cache: Option<ReleaseCache>) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Lists releases of every registered source at once. Sources with cached releases
+    /// aren't queried, even if the cache is stale, so call [`revalidate`](BepInEx::revalidate)
+    /// afterwards to refresh those. Releases are deduplicated by flavor and version (sources
+    /// registered first win) and sorted newest first.
+    pub fn fetch(&mut self) -> &mut Self {
+        block_on(self.fetch_with(Io::Blocking))
+    }
+
+    /// Queries the sources whose cached releases are stale. The cached releases are kept
+    /// if that fails.
+    pub fn revalidate(&mut self) -> &mut Self {
+        block_on(self.revalidate_with(Io::Blocking))
+    }
+
+    /// Async [`fetch`](BepInEx::fetch), querying the sources concurrently on the current task
+    #[cfg(feature = "async")]
+    pub async fn fetch_async(&mut self) -> &mut Self {
+        self.fetch_with(Io::Async).await
+    }
+
+    /// Async [`revalidate`](BepInEx::revalidate)
+    #[cfg(feature = "async")]
+    pub async fn revalidate_async(&mut self) -> &mut Self {
+        self.revalidate_with(Io::Async).await
+    }
+
+    async fn fetch_with(&mut self, io: Io) -> &mut Self {
+        let all = (0..self.sources.len()).collect::<Vec<_>>();
+        let results = self.load(io, &all, false).await;
+
+        self.fetched = vec![None; self.sources.len()];
+        self.failures = vec![None; self.sources.len()];
+        self.apply(results);
+        self
+    }
+
+    async fn revalidate_with(&mut self, io: Io) -> &mut Self {
+        if !self.needs_revalidation() {
+            return self;
+        }
+
+        let results = self.load(io, &self.stale_sources(), true).await;
+        self.apply(results);
+        self
+    }
+
+    /// Indices of the sources whose releases came from a stale cache
+    fn stale_sources(&self) -> Vec<usize> {
+        self.fetched
+            .iter()
+            .enumerate()
+            .filter(|(_, fetched)| fetched.as_ref().map(|f| f.status.stale).unwrap_or(false))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Some releases came from a stale cache and the cache isn't offline
+    pub fn needs_revalidation(&self) -> bool {
+        let offline = self.cache.as_ref().map(|c| c.is_offline()).unwrap_or(false);
+        !offline && self.statuses().any(|status| status.stale)
+    }
+
+    /// Status of every source that returned releases
+    pub fn statuses(&self) -> impl Iterator<Item = &SourceStatus> {
+        self.fetched.iter().flatten().map(|fetched| &fetched.status)
+    }
+
+    /// Blocking sources are queried on a thread each, async ones concurrently on the
+    /// current task
+    async fn load(
+        &self,
+        io: Io,
+        indices: &[usize],
+        revalidate: bool,
+    ) -> Vec<(usize, anyhow::Result<FetchedReleases>)> {
+        match io {
+            Io::Blocking => thread::scope(|scope| {
+                let handles = indices
+                    .iter()
+                    .map(|&index| {
+                        let source = self.sources[index].as_ref();
+                        scope.spawn(move || {
+                            let result = self.load_source(io, source, revalidate);
+                            (index, block_on(result))
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("Source panicked"))
+                    .collect()
+            }),
+            #[cfg(feature = "async")]
+            Io::Async => {
+                join_all(indices.iter().map(|&index| async move {
+                    let source = self.sources[index].as_ref();
+                    (index, self.load_source(io, source, revalidate).await)
+                }))
+                .await
+            }
+        }
+    }
+
+    /// Cached releases unless `revalidate` is set, the source's releases otherwise
+    async fn load_source(
+        &self,
+        io: Io,
+        source: &dyn ReleaseSource,
+        revalidate: bool,
+    ) -> anyhow::Result<FetchedReleases> {
+        if let Some(cached) = self.read_cache(source, revalidate)? {
+            return Ok(cached);
+        }
+
+        let releases = match io {
+            Io::Blocking => source.fetch()?,
+            #[cfg(feature = "async")]
+            Io::Async => source.fetch_async().await?,
+        };
+        Ok(self.write_cache(source, releases))
+    }
+
+    /// The source's cached releases, `None` if it has to be queried
+    fn read_cache(
+        &self,
+        source: &dyn ReleaseSource,
+        revalidate: bool,
+    ) -> anyhow::Result<Option<FetchedReleases>> {
+        let cache = match &self.cache {
+            Some(cache) if source.cacheable() => cache,
+            _ => return Ok(None),
+        };
+
+        match cache.read(&source.cache_key()) {
+            Some(cached) if cache.is_offline() || !revalidate => Ok(Some(FetchedReleases {
+                status: SourceStatus {
+                    source_id: source.id(),
+                    fetched_at: cached.fetched_at(),
+                    from_cache: true,
+                    stale: cache.is_stale(&cached),
+                },
+                releases: cached.releases,
+                skipped: Vec::new(),
+            })),
+            None if cache.is_offline() => anyhow::bail!("Offline and no releases are cached"),
+            _ => Ok(None),
+        }
+    }
+
+    /// Caches releases the source just returned
+    fn write_cache(&self, source: &dyn ReleaseSource, fetched: SourceReleases) -> FetchedReleases {
+        let SourceReleases { releases, skipped } = fetched;
+        let fetched_at = SystemTime::now();
+        let write = match &self.cache {
+            Some(cache) if source.cacheable() => {
+                Some(cache.write(&source.cache_key(), releases.clone(), fetched_at))
+            }
+            _ => None,
+        };
+        // A cache that can't be written only costs a refetch next time
+        let releases = match write {
+            Some(Ok(cached)) => cached.releases,
+            _ => releases,
+        };
+        FetchedReleases {
+            releases,
+            status: SourceStatus {
+                source_id: source.id(),
+                fetched_at,
+                from_cache: false,
+                stale: false,
+            },
+            skipped: skipped
+                .into_iter()
+                .map(|error| SourceError {
+                    source_id: source.id(),
+                    error: Arc::new(error),
+                })
+                .collect(),
+        }
+    }
+
+    fn apply(&mut self, results: Vec<(usize, anyhow::Result<FetchedReleases>)>) {
+        for (index, result) in results {
+            match result {
+                Ok(fetched) => {
+                    self.fetched[index] = Some(fetched);
+                    self.failures[index] = None;
+                }
+                Err(error) => {
+                    self.failures[index] = Some(SourceError {
+                        source_id: self.sources[index].id(),
+                        error: Arc::new(error),
+                    })
+                }
+            }
+        }
+        // Sources that failed to revalidate keep their releases, and with them what they
+        // skipped
+        self.errors = self
+            .fetched
+            .iter()
+            .zip(self.failures.iter())
+            .flat_map(|(fetched, failure)| {
+                let skipped = fetched.iter().flat_map(|fetched| fetched.skipped.iter());
+                skipped.chain(failure).cloned()
+            })
+            .collect();
+
+        let mut releases: Vec<BepInExRelease> = Vec::new();
+        for fetched in self.fetched.iter().flatten() {
+            for release in fetched.releases.iter() {
+                if releases
+                    .iter()
+                    .any(|r| r.flavor == release.flavor && r.version == release.version)
+                {
+                    continue;
+                }
+                let mut release = release.clone();
+                release.source = fetched.status.source_id.clone();
+                releases.push(release);
+            }
+        }
+        releases.sort_by(|a, b| b.cmp_version(a));
+        self.releases = releases;
+    }
+
+    /// Newest stable release that isn't a pre-release
     pub fn latest(&self) -> Option<BepInExRelease> {
         self.releases
             .iter()
-            .filter(|r| r.flavor == ReleaseFlavor::Stable)
-            .map(|r| r.to_owned())
-            .collect::<Vec<_>>()
-            .first()
+            .find(|r| r.flavor == ReleaseFlavor::Stable && r.version.pre.is_empty())
             .map(|r| r.to_owned())
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ReleaseFlavor {
+    #[default]
     Stable,
     BleedingEdge,
 }
@@ -41,70 +349,209 @@ impl Display for ReleaseFlavor {
     }
 }
 
-impl Default for ReleaseFlavor {
-    fn default() -> Self {
-        Self::Stable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BepInExAsset {
+    pub name: String,
+    pub link: String,
+    /// Size in bytes, if the source knows it
+    pub size: Option<u64>,
+    /// Hex encoded SHA-256 of the archive, if the source knows it
+    pub sha256: Option<String>,
+}
+
+impl BepInExAsset {
+    pub fn descriptor(&self) -> Option<AssetDescriptor> {
+        AssetDescriptor::parse(&self.name)
+    }
+
+    /// Checks the archive against the SHA-256 the source gave, if any
+    pub fn verify(&self, bytes: &[u8]) -> Result<(), ChecksumMismatch> {
+        self.verify_sha256(&sha256_hex(bytes))
+    }
+
+    /// Like [`verify`](BepInExAsset::verify) for an already computed hex SHA-256
+    pub fn verify_sha256(&self, actual: &str) -> Result<(), ChecksumMismatch> {
+        match &self.sha256 {
+            Some(expected) if !actual.eq_ignore_ascii_case(expected) => Err(ChecksumMismatch {
+                name: self.name.clone(),
+                expected: expected.clone(),
+                actual: actual.to_owned(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the archive from disk for `file://` links or downloads it otherwise
+    pub fn get_bytes(&self, client: &SourcesClient) -> anyhow::Result<Vec<u8>> {
+        block_on(self.get_bytes_with(Io::Blocking, client))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_bytes_async(&self, client: &SourcesClient) -> anyhow::Result<Vec<u8>> {
+        self.get_bytes_with(Io::Async, client).await
+    }
+
+    pub(crate) async fn get_bytes_with(
+        &self,
+        io: Io,
+        client: &SourcesClient,
+    ) -> anyhow::Result<Vec<u8>> {
+        let bytes = match self.link.starts_with("file://") {
+            true => {
+                let path = self.file_path()?;
+                io.run_blocking(move || Ok(fs::read(path)?)).await?
+            }
+            false => {
+                client
+                    .get_with(io, &self.link)
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await?
+            }
+        };
+        self.verify(&bytes)?;
+        Ok(bytes)
+    }
+
+    /// Async [`AssetDownloader::download`], extracting on tokio's blocking threads
+    #[cfg(feature = "async")]
+    pub async fn download_async(
+        &self,
+        game: &Game,
+        downloader: &Downloader,
+    ) -> anyhow::Result<ExtractionPlan> {
+        self.download_with(Io::Async, game, downloader).await
+    }
+
+    async fn download_with(
+        &self,
+        io: Io,
+        game: &Game,
+        downloader: &Downloader,
+    ) -> anyhow::Result<ExtractionPlan> {
+        let archive = downloader.download_with(io, self).await?;
+        let game = game.clone();
+        io.run_blocking(move || archive.extract(&game)).await
+    }
+
+    /// Path of a `file://` link
+    pub(crate) fn file_path(&self) -> anyhow::Result<PathBuf> {
+        Url::parse(&self.link)?
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("Invalid file link {}", self.link))
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BepInExAsset {
+/// Downloaded archive doesn't match the SHA-256 its source published
+#[derive(Debug, Clone)]
+pub struct ChecksumMismatch {
     pub name: String,
-    pub link: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is corrupted, expected SHA-256 {} but got {}",
+            self.name, self.expected, self.actual
+        )
+    }
+}
+
+impl error::Error for ChecksumMismatch {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseCommit {
+    pub hash: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl ReleaseCommit {
+    /// First line of the commit message
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
+impl From<BuildsCommit> for ReleaseCommit {
+    fn from(commit: BuildsCommit) -> Self {
+        Self {
+            hash: commit.hash,
+            message: commit.message,
+            author: commit.author,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BepInExRelease {
     pub version: Version,
     pub assets: Vec<BepInExAsset>,
     pub flavor: ReleaseFlavor,
+    /// Id of the [`ReleaseSource`] this release came from
+    pub source: String,
+    /// RFC 3339 date the release was built or published, if the source knows it
+    pub date: Option<String>,
+    pub branch: Option<String>,
+    /// Commits that went into this release, newest first
+    pub commits: Vec<ReleaseCommit>,
+    /// Release notes, in markdown
+    pub notes: Option<String>,
+    /// Web page of the release
+    pub url: Option<String>,
 }
 
 impl BepInExRelease {
-    pub fn select_asset(&self, query: String) -> Option<BepInExAsset> {
-        self.assets
-            .iter()
-            .find(|a| a.name == query)
-            .map(|a| a.to_owned())
+    /// Orders by version, with stable releases before bleeding edge builds of the same version
+    pub fn cmp_version(&self, other: &Self) -> Ordering {
+        cmp_precedence(&self.version, &other.version).then_with(|| {
+            match (&self.flavor, &other.flavor) {
+                (ReleaseFlavor::Stable, ReleaseFlavor::BleedingEdge) => Ordering::Greater,
+                (ReleaseFlavor::BleedingEdge, ReleaseFlavor::Stable) => Ordering::Less,
+                _ => self.version.build.cmp(&other.version.build),
+            }
+        })
     }
 
-    pub fn to_query(&self, game: &Game) -> String {
+    /// builds.bepinex.dev artifact number, e.g. 674 for `6.0.0-be.674+82077ec`
+    pub fn artifact_id(&self) -> Option<usize> {
         match self.flavor {
-            ReleaseFlavor::Stable => match self.version.major {
-                6 => format!(
-                    "BepInEx_{}_{}_{}.zip",
-                    game_type!(game.ty),
-                    game.arch,
-                    &self.version
-                ),
-                _ => format!("BepInEx_{}_{}.0.zip", game.arch, self),
-            },
-            ReleaseFlavor::BleedingEdge => {
-                let artifact_id = self
-                    .version
-                    .pre
-                    .split('.')
-                    .filter_map(|e| e.parse::<u32>().ok())
-                    .collect::<Vec<_>>()[0];
-                match artifact_id >= 600 {
-                    true => format!(
-                        "BepInEx-{}-win-{}-{}.zip",
-                        game.ty.as_ref().unwrap(),
-                        game.arch,
-                        self.version,
-                    ),
-                    false => {
-                        format!(
-                            "BepInEx_{}_{}_{}_{}.zip",
-                            game_type!(game.ty),
-                            game.arch,
-                            self.version.build,
-                            self.version.mmpp()
-                        )
-                    }
-                }
-            }
+            ReleaseFlavor::Stable => None,
+            ReleaseFlavor::BleedingEdge => self
+                .version
+                .pre
+                .split('.')
+                .find_map(|e| e.parse::<usize>().ok()),
+        }
+    }
+
+    /// Human friendly name for version pickers, e.g. `#674 – 2023-03-01 – Update Il2CppInterop`
+    /// for bleeding edge builds and `5.4.21 – 2022-08-10` for releases.
+    pub fn label(&self) -> String {
+        let mut parts = vec![match self.artifact_id() {
+            Some(id) => format!("#{id}"),
+            None => self.version.to_string(),
+        }];
+        if let Some(date) = &self.date {
+            parts.push(date.chars().take(10).collect());
         }
+        if let Some(commit) = self.commits.first() {
+            parts.push(commit.subject().to_owned());
+        }
+        parts.join(" – ")
+    }
+
+    /// First asset built for the game's runtime, platform and arch
+    pub fn select_asset(&self, game: &Game) -> Option<BepInExAsset> {
+        self.assets
+            .iter()
+            .find(|a| a.descriptor().map(|d| d.matches(game)).unwrap_or_default())
+            .map(|a| a.to_owned())
     }
 }
 
@@ -118,9 +565,19 @@ impl From<GitHubRelease> for BepInExRelease {
                 .map(|r| BepInExAsset {
                     name: r.name,
                     link: r.browser_download_url,
+                    size: Some(r.size),
+                    sha256: r
+                        .digest
+                        .and_then(|digest| digest.strip_prefix("sha256:").map(str::to_owned)),
                 })
                 .collect(),
             flavor: ReleaseFlavor::Stable,
+            source: github::SOURCE_ID.to_owned(),
+            date: rel.published_at,
+            branch: None,
+            commits: Vec::new(),
+            notes: rel.body.filter(|body| !body.trim().is_empty()),
+            url: Some(rel.html_url).filter(|url| !url.is_empty()),
         }
     }
 }
@@ -135,9 +592,17 @@ impl From<BuildsRelease> for BepInExRelease {
                 .map(|r| BepInExAsset {
                     name: r.name,
                     link: r.link,
+                    size: r.size,
+                    sha256: None,
                 })
                 .collect(),
             flavor: ReleaseFlavor::BleedingEdge,
+            source: builds::SOURCE_ID.to_owned(),
+            date: rel.date,
+            branch: rel.branch,
+            commits: rel.commits.into_iter().map(ReleaseCommit::from).collect(),
+            notes: None,
+            url: None,
         }
     }
 }
@@ -149,17 +614,12 @@ impl Display for BepInExRelease {
 }
 
 pub trait AssetDownloader {
-    fn download(&self, game: &Game) -> anyhow::Result<()>;
+    /// Downloads and extracts the archive into the game, returning the files written
+    fn download(&self, game: &Game, downloader: &Downloader) -> anyhow::Result<ExtractionPlan>;
 }
 
 impl AssetDownloader for BepInExAsset {
-    fn download(&self, game: &Game) -> anyhow::Result<()> {
-        let client = reqwest::blocking::Client::new();
-        let resp = client.get(&self.link).send()?.bytes()?;
-
-        let content = Cursor::new(resp.to_vec());
-        ZipArchive::new(content)?.extract(&game.path)?;
-
-        Ok(())
+    fn download(&self, game: &Game, downloader: &Downloader) -> anyhow::Result<ExtractionPlan> {
+        block_on(self.download_with(Io::Blocking, game, downloader))
     }
 }
@@ -1,22 +1,158 @@
+use std::{error, fmt::Display};
+
+#[cfg(feature = "async")]
+use futures_util::future::BoxFuture;
 use lazy_static::lazy_static;
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use semver::Version;
 
 use crate::{
-    models::bleeding_edge::builds::{BuildsAsset, BuildsRelease},
-    s_parse, select,
+    bepinex::BepInExRelease,
+    client::SourcesClient,
+    models::bleeding_edge::builds::{BuildsAsset, BuildsCommit, BuildsRelease},
+    s_parse,
+    source::{ReleaseSource, SourceReleases},
+    transport::{block_on, Io},
 };
 
+pub const SOURCE_ID: &str = "builds";
+
 lazy_static! {
     static ref VERISON_REGEX: Regex = Regex::new(
             r"((?:0|[1-9]\d*)\.(?:0|[1-9]\d*)\.(?:0|[1-9]\d*)-(?:(?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*)(?:\.(?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*))))",
     ).unwrap();
 }
 
+/// Something on the builds page didn't look like expected
+#[derive(Debug)]
+pub enum BuildsError {
+    /// `selector` matched nothing, inside the given artifact if known
+    MissingElement {
+        artifact_id: Option<usize>,
+        selector: &'static str,
+    },
+    InvalidArtifactId(String),
+    /// None of the artifact's files has a version in its name
+    MissingVersion(usize),
+    InvalidVersion {
+        artifact_id: usize,
+        version: String,
+        error: semver::Error,
+    },
+}
+
+impl Display for BuildsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildsError::MissingElement {
+                artifact_id: Some(id),
+                selector,
+            } => write!(f, "Artifact #{id}: no element matches `{selector}`"),
+            BuildsError::MissingElement {
+                artifact_id: None,
+                selector,
+            } => write!(f, "No element matches `{selector}`"),
+            BuildsError::InvalidArtifactId(text) => write!(f, "Invalid artifact id `{text}`"),
+            BuildsError::MissingVersion(id) => {
+                write!(f, "Artifact #{id}: no file name contains a version")
+            }
+            BuildsError::InvalidVersion {
+                artifact_id,
+                version,
+                error,
+            } => write!(
+                f,
+                "Artifact #{artifact_id}: invalid version `{version}`: {error}"
+            ),
+        }
+    }
+}
+
+impl error::Error for BuildsError {}
+
+/// Builds listed on the page, along with the artifacts that had to be skipped
+#[derive(Debug, Default)]
+pub struct BuildsPage {
+    pub releases: Vec<BuildsRelease>,
+    pub skipped: Vec<BuildsError>,
+}
+
+struct Selectors {
+    artifact_item: Selector,
+    build_id: Selector,
+    artifact_hash: Selector,
+    artifacts_list: Selector,
+    /// Links and the sizes following them, in page order
+    artifact_file: Selector,
+    build_date: Selector,
+    build_branch: Selector,
+    commit: Selector,
+    commit_hash: Selector,
+    commit_author: Selector,
+}
+
+fn select_first<'a>(
+    el: ElementRef<'a>,
+    selector: &Selector,
+    name: &'static str,
+    artifact_id: Option<usize>,
+) -> Result<ElementRef<'a>, BuildsError> {
+    el.select(selector)
+        .next()
+        .ok_or(BuildsError::MissingElement {
+            artifact_id,
+            selector: name,
+        })
+}
+
+fn element_text(el: ElementRef) -> String {
+    el.text().collect::<String>().trim().to_owned()
+}
+
+fn optional_text(el: ElementRef, selector: &Selector) -> Option<String> {
+    el.select(selector)
+        .next()
+        .map(element_text)
+        .filter(|text| !text.is_empty())
+}
+
+/// Parses sizes like `43.52 MiB` or `704 KB`
+pub fn parse_size(text: &str) -> Option<u64> {
+    let (number, unit) = text.trim().split_once(' ')?;
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.trim() {
+        "B" => 1,
+        "KiB" | "KB" | "kB" => 1 << 10,
+        "MiB" | "MB" => 1 << 20,
+        "GiB" | "GB" => 1 << 30,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+/// `<li><a>82077ec</a> Update Il2CppInterop - <i>js6pak</i></li>`
+fn parse_commit(el: ElementRef, selectors: &Selectors) -> Option<BuildsCommit> {
+    let hash = optional_text(el, &selectors.commit_hash)?;
+    let message = el
+        .children()
+        .filter_map(|node| node.value().as_text())
+        .map(|text| &**text)
+        .collect::<String>();
+    let message = message.trim().trim_end_matches('-').trim().to_owned();
+
+    Some(BuildsCommit {
+        hash,
+        message,
+        author: optional_text(el, &selectors.commit_author),
+    })
+}
+
+#[derive(Debug)]
 pub struct BuildsApi {
     base_url: String,
     min_build_id: Option<usize>,
+    client: SourcesClient,
 }
 
 impl BuildsApi {
@@ -24,6 +160,7 @@ impl BuildsApi {
         BuildsApi {
             base_url: base_url.into(),
             min_build_id: None,
+            client: SourcesClient::default(),
         }
     }
 
@@ -32,71 +169,206 @@ impl BuildsApi {
         self
     }
 
+    pub fn set_client(&mut self, client: SourcesClient) -> &mut Self {
+        self.client = client;
+        self
+    }
+
     pub fn set_min_build_id(&mut self, min_build: Option<usize>) -> &mut Self {
         self.min_build_id = min_build;
         self
     }
 
     pub fn filter_builds(&self, build: &BuildsRelease) -> bool {
+        self.filter_artifact_id(build.artifact_id)
+    }
+
+    fn filter_artifact_id(&self, artifact_id: usize) -> bool {
         match &self.min_build_id {
-            Some(build_id) => build.artifact_id >= *build_id,
+            Some(build_id) => artifact_id >= *build_id,
             None => true,
         }
     }
 
     pub fn get_builds(&self) -> anyhow::Result<Vec<BuildsRelease>> {
-        let mut releases: Vec<BuildsRelease> = Vec::new();
-
-        let resp = reqwest::blocking::Client::new()
-            .get(format!("{}/projects/bepinex_be", &self.base_url))
-            .send()?;
-        let html = resp.text()?;
-        let fragment = Html::parse_fragment(&html);
-
-        let main_selector = s_parse!("main");
-        let artifact_item_selector = s_parse!("div.artifact-item");
-        let build_id_selector = s_parse!("span.artifact-id");
-        let artifact_hash_selector = s_parse!("a.hash-button");
-
-        let artifacts_list_selector = s_parse!("div.artifacts-list");
-        let artifact_link_selector = s_parse!("a.artifact-link");
-
-        let main = select!(fragment, &main_selector);
-        for el in main.select(&artifact_item_selector) {
-            let artifact_id = select!(el, &build_id_selector)
-                .text()
-                .filter_map(|e| e[1..].parse::<usize>().ok())
-                .collect::<Vec<_>>()[0];
-            let build_hash = select!(el, &artifact_hash_selector)
-                .text()
-                .collect::<Vec<_>>()[0]
-                .to_string();
-            let mut version: String = "".into();
-
-            let mut assets: Vec<BuildsAsset> = Vec::new();
-            let artifacts_list = select!(el, &artifacts_list_selector);
-            for artifact_el in artifacts_list.select(&artifact_link_selector) {
-                let download_link = artifact_el.value().attr("href").unwrap();
-
-                let artifact_name = artifact_el.text().collect::<Vec<&str>>()[0].to_string();
-                if version.is_empty() && let Some(version_m) = VERISON_REGEX.find(&artifact_name) {
-                    version = format!("{}+{}", artifact_name[version_m.start()..version_m.end()].to_owned(), build_hash);
+        Ok(self.get_page()?.releases)
+    }
+
+    pub fn get_page(&self) -> anyhow::Result<BuildsPage> {
+        block_on(self.get_page_with(Io::Blocking))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_builds_async(&self) -> anyhow::Result<Vec<BuildsRelease>> {
+        Ok(self.get_page_async().await?.releases)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_page_async(&self) -> anyhow::Result<BuildsPage> {
+        self.get_page_with(Io::Async).await
+    }
+
+    async fn get_page_with(&self, io: Io) -> anyhow::Result<BuildsPage> {
+        let html = self
+            .client
+            .get_with(io, &self.page_url())
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(self.parse_page(&html)?)
+    }
+
+    fn page_url(&self) -> String {
+        format!("{}/projects/bepinex_be", self.base_url)
+    }
+
+    /// Parses the HTML of `/projects/bepinex_be`. Artifacts that can't be parsed are skipped
+    /// and reported in [`BuildsPage::skipped`], only a page without a `<main>` is an error.
+    pub fn parse_page(&self, html: &str) -> Result<BuildsPage, BuildsError> {
+        let fragment = Html::parse_fragment(html);
+        let selectors = Selectors {
+            artifact_item: s_parse!("div.artifact-item"),
+            build_id: s_parse!("span.artifact-id"),
+            artifact_hash: s_parse!("a.hash-button"),
+            artifacts_list: s_parse!("div.artifacts-list"),
+            artifact_file: s_parse!("a.artifact-link, span.artifact-size"),
+            build_date: s_parse!("span.build-date"),
+            build_branch: s_parse!("span.build-branch"),
+            commit: s_parse!("div.changelog li"),
+            commit_hash: s_parse!("a"),
+            commit_author: s_parse!("i"),
+        };
+
+        let main = select_first(fragment.root_element(), &s_parse!("main"), "main", None)?;
+
+        let mut page = BuildsPage::default();
+        for el in main.select(&selectors.artifact_item) {
+            match self.parse_artifact(el, &selectors) {
+                Ok(Some(release)) => page.releases.push(release),
+                Ok(None) => {}
+                Err(e) => page.skipped.push(e),
+            }
+        }
+
+        Ok(page)
+    }
+
+    /// `Ok(None)` for artifacts older than the minimum build id
+    fn parse_artifact(
+        &self,
+        el: ElementRef,
+        selectors: &Selectors,
+    ) -> Result<Option<BuildsRelease>, BuildsError> {
+        let id_text = element_text(select_first(
+            el,
+            &selectors.build_id,
+            "span.artifact-id",
+            None,
+        )?);
+        let artifact_id = id_text
+            .trim_start_matches('#')
+            .parse::<usize>()
+            .map_err(|_| BuildsError::InvalidArtifactId(id_text.clone()))?;
+        if !self.filter_artifact_id(artifact_id) {
+            return Ok(None);
+        }
+
+        let build_hash = element_text(select_first(
+            el,
+            &selectors.artifact_hash,
+            "a.hash-button",
+            Some(artifact_id),
+        )?);
+        let artifacts_list = select_first(
+            el,
+            &selectors.artifacts_list,
+            "div.artifacts-list",
+            Some(artifact_id),
+        )?;
+
+        let mut version: Option<String> = None;
+        let mut assets: Vec<BuildsAsset> = Vec::new();
+        for artifact_el in artifacts_list.select(&selectors.artifact_file) {
+            if artifact_el.value().name() != "a" {
+                if let Some(asset) = assets.last_mut() {
+                    asset.size = parse_size(&element_text(artifact_el));
                 }
-                assets.push(BuildsAsset {
-                    name: artifact_name,
-                    link: format!("{}{}", self.base_url, download_link),
-                });
+                continue;
             }
-            releases.push(BuildsRelease {
-                artifact_id,
-                version: Version::parse(&version).unwrap(),
-                assets,
+
+            let download_link = match artifact_el.value().attr("href") {
+                Some(link) => link,
+                None => continue,
+            };
+            let artifact_name = element_text(artifact_el);
+
+            if version.is_none() {
+                version = VERISON_REGEX
+                    .find(&artifact_name)
+                    .map(|m| format!("{}+{}", m.as_str(), build_hash));
+            }
+            assets.push(BuildsAsset {
+                name: artifact_name,
+                link: format!("{}{}", self.base_url, download_link),
+                size: None,
             });
         }
 
-        Ok(releases
-            .into_iter()
-            .filter(|b| self.filter_builds(b))
-            .collect())
+        let version = version.ok_or(BuildsError::MissingVersion(artifact_id))?;
+        let version = Version::parse(&version).map_err(|error| BuildsError::InvalidVersion {
+            artifact_id,
+            version,
+            error,
+        })?;
+
+        Ok(Some(BuildsRelease {
+            artifact_id,
+            version,
+            assets,
+            date: optional_text(el, &selectors.build_date),
+            branch: optional_text(el, &selectors.build_branch),
+            commits: el
+                .select(&selectors.commit)
+                .filter_map(|commit| parse_commit(commit, selectors))
+                .collect(),
+        }))
+    }
+}
+
+impl ReleaseSource for BuildsApi {
+    fn id(&self) -> String {
+        SOURCE_ID.to_owned()
     }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases> {
+        into_releases(self.get_page()?)
+    }
+
+    #[cfg(feature = "async")]
+    fn fetch_async(&self) -> BoxFuture<'_, anyhow::Result<SourceReleases>> {
+        Box::pin(async move { into_releases(self.get_page_async().await?) })
+    }
+}
+
+/// Skipped artifacts are passed on, unless nothing could be parsed at all
+fn into_releases(page: BuildsPage) -> anyhow::Result<SourceReleases> {
+    let mut skipped = page.skipped.into_iter().map(anyhow::Error::from);
+    // A page where every artifact got skipped most likely means the markup changed
+    if page.releases.is_empty() {
+        return match skipped.next() {
+            Some(e) => Err(e),
+            None => Ok(SourceReleases::default()),
+        };
+    }
+
+    Ok(SourceReleases {
+        releases: page
+            .releases
+            .into_iter()
+            .map(BepInExRelease::from)
+            .collect(),
+        skipped: skipped.collect(),
+    })
 }
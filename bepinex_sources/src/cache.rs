@@ -0,0 +1,366 @@
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bepinex::{BepInExAsset, BepInExRelease},
+    hash::{sha256_file, sha256_hex},
+};
+
+/// Bumped when cached releases change shape, files of other versions are ignored
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// How long cached releases are used before the source is queried again
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Least recently used archives are evicted above this, 2 GiB
+pub const DEFAULT_MAX_ARTIFACTS_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+const ARTIFACT_INDEX: &str = "index.json";
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Modification time in nanoseconds since the Unix epoch
+fn modified_nanos(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(modified.as_nanos() as u64)
+}
+
+/// Removes a cache directory, returning how many bytes were freed
+fn remove_dir(dir: &Path) -> anyhow::Result<u64> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let size = entries
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    fs::remove_dir_all(dir)?;
+    Ok(size)
+}
+
+/// Releases of one source as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedReleases {
+    pub format_version: u32,
+    /// Unix time the releases were fetched from the source
+    pub fetched_at: u64,
+    pub releases: Vec<BepInExRelease>,
+}
+
+impl CachedReleases {
+    pub fn fetched_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.fetched_at)
+    }
+
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.fetched_at())
+            .unwrap_or_default()
+    }
+}
+
+/// On-disk cache of release lists, one JSON file per source
+#[derive(Debug, Clone)]
+pub struct ReleaseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    offline: bool,
+}
+
+impl ReleaseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ReleaseCache {
+            dir: dir.into(),
+            ttl: DEFAULT_TTL,
+            offline: false,
+        }
+    }
+
+    pub fn set_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Never query sources, cached releases are used no matter how old they are
+    pub fn set_offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn is_stale(&self, cached: &CachedReleases) -> bool {
+        cached.age() > self.ttl
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// `None` if nothing is cached for the key or the file can't be read
+    pub fn read(&self, key: &str) -> Option<CachedReleases> {
+        let text = fs::read_to_string(self.path(key)).ok()?;
+        serde_json::from_str::<CachedReleases>(&text)
+            .ok()
+            .filter(|cached| cached.format_version == CACHE_FORMAT_VERSION)
+    }
+
+    pub fn write(
+        &self,
+        key: &str,
+        releases: Vec<BepInExRelease>,
+        fetched_at: SystemTime,
+    ) -> anyhow::Result<CachedReleases> {
+        let cached = CachedReleases {
+            format_version: CACHE_FORMAT_VERSION,
+            fetched_at: fetched_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            releases,
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        // Written next to the cache file first so readers never see half a file
+        let path = self.path(key);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&cached)?)?;
+        fs::rename(tmp, path)?;
+        Ok(cached)
+    }
+
+    /// Removes every cached release list, returning how many bytes were freed
+    pub fn clear(&self) -> anyhow::Result<u64> {
+        remove_dir(&self.dir)
+    }
+}
+
+/// Archive kept in an [`ArtifactCache`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedArtifact {
+    pub sha256: String,
+    pub size: u64,
+    /// Links the archive was downloaded from
+    pub links: Vec<String>,
+    /// Unix time in milliseconds the archive was last stored or read, the oldest ones are
+    /// evicted first
+    pub last_used: u64,
+    /// Modification time of the stored file in nanoseconds, it's only hashed again when
+    /// this or the size changed. `0` if unknown.
+    #[serde(default)]
+    pub modified: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ArtifactIndex {
+    artifacts: Vec<CachedArtifact>,
+}
+
+/// Downloaded archives stored by their SHA-256, so installing the same build into several
+/// games only downloads it once
+#[derive(Debug, Clone)]
+pub struct ArtifactCache {
+    dir: PathBuf,
+    max_size: u64,
+    /// Serializes index updates between clones
+    lock: Arc<Mutex<()>>,
+}
+
+impl ArtifactCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ArtifactCache {
+            dir: dir.into(),
+            max_size: DEFAULT_MAX_ARTIFACTS_SIZE,
+            lock: Arc::default(),
+        }
+    }
+
+    /// Total size in bytes the archives may take, the most recently used one is always kept
+    pub fn set_max_size(&mut self, max_size: u64) -> &mut Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    fn artifact_path(&self, sha256: &str) -> PathBuf {
+        self.dir.join(format!("{}.zip", sha256))
+    }
+
+    fn read_index(&self) -> ArtifactIndex {
+        fs::read_to_string(self.dir.join(ARTIFACT_INDEX))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_index(&self, index: &ArtifactIndex) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(ARTIFACT_INDEX),
+            serde_json::to_vec_pretty(index)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn artifacts(&self) -> Vec<CachedArtifact> {
+        let _guard = self.lock.lock().unwrap();
+        self.read_index().artifacts
+    }
+
+    /// Bytes taken by cached archives
+    pub fn size(&self) -> u64 {
+        self.artifacts().iter().map(|artifact| artifact.size).sum()
+    }
+
+    /// Archive of the asset, looked up by its SHA-256 if the source gave one and by link
+    /// otherwise. Archives that no longer match their hash are dropped.
+    pub fn get(&self, asset: &BepInExAsset) -> Option<Vec<u8>> {
+        fs::read(self.get_path(asset)?).ok()
+    }
+
+    /// Like [`get`](ArtifactCache::get) but returns where the archive is stored
+    pub fn get_path(&self, asset: &BepInExAsset) -> Option<PathBuf> {
+        let _guard = self.lock.lock().unwrap();
+        let mut index = self.read_index();
+        let position = index
+            .artifacts
+            .iter()
+            .position(|artifact| match &asset.sha256 {
+                Some(sha256) => artifact.sha256.eq_ignore_ascii_case(sha256),
+                None => artifact.links.contains(&asset.link),
+            })?;
+
+        let artifact = &index.artifacts[position];
+        let path = self.artifact_path(&artifact.sha256);
+        // Archives are several MB, hashing them on every lookup would stall whoever asks
+        let metadata = fs::metadata(&path).ok();
+        let modified = metadata.as_ref().and_then(modified_nanos);
+        let unchanged = artifact.modified != 0
+            && modified == Some(artifact.modified)
+            && metadata.map(|metadata| metadata.len()) == Some(artifact.size);
+        let intact = unchanged
+            || sha256_file(&path)
+                .map(|sha256| sha256 == artifact.sha256)
+                .unwrap_or(false);
+        match intact {
+            true => {
+                let artifact = &mut index.artifacts[position];
+                artifact.last_used = unix_millis();
+                artifact.modified = modified.unwrap_or_default();
+            }
+            false => {
+                let _ = fs::remove_file(&path);
+                index.artifacts.remove(position);
+            }
+        }
+        // Only costs the LRU order or a stale entry
+        let _ = self.write_index(&index);
+        intact.then_some(path)
+    }
+
+    /// Stores the archive after checking it against the asset's SHA-256 and evicts the least
+    /// recently used archives above the size limit
+    pub fn put(&self, asset: &BepInExAsset, bytes: &[u8]) -> anyhow::Result<PathBuf> {
+        let sha256 = sha256_hex(bytes);
+        self.insert(asset, &sha256, bytes.len() as u64, |tmp| {
+            fs::write(tmp, bytes)
+        })
+    }
+
+    /// Like [`put`](ArtifactCache::put) for an archive on disk, which is copied
+    pub fn put_file(&self, asset: &BepInExAsset, path: &Path) -> anyhow::Result<PathBuf> {
+        let sha256 = sha256_file(path)?;
+        let size = fs::metadata(path)?.len();
+        self.insert(asset, &sha256, size, |tmp| fs::copy(path, tmp).map(|_| ()))
+    }
+
+    fn insert(
+        &self,
+        asset: &BepInExAsset,
+        sha256: &str,
+        size: u64,
+        write: impl FnOnce(&Path) -> io::Result<()>,
+    ) -> anyhow::Result<PathBuf> {
+        asset.verify_sha256(sha256)?;
+
+        let _guard = self.lock.lock().unwrap();
+        let mut index = self.read_index();
+        let path = self.artifact_path(sha256);
+        if !path.exists() {
+            fs::create_dir_all(&self.dir)?;
+            let tmp = path.with_extension("zip.tmp");
+            write(&tmp)?;
+            fs::rename(tmp, &path)?;
+        }
+        let modified = modified_nanos(&fs::metadata(&path)?).unwrap_or_default();
+
+        match index.artifacts.iter_mut().find(|a| a.sha256 == sha256) {
+            Some(artifact) => {
+                if !artifact.links.contains(&asset.link) {
+                    artifact.links.push(asset.link.clone());
+                }
+                artifact.last_used = unix_millis();
+                artifact.modified = modified;
+            }
+            None => index.artifacts.push(CachedArtifact {
+                sha256: sha256.to_owned(),
+                size,
+                links: vec![asset.link.clone()],
+                last_used: unix_millis(),
+                modified,
+            }),
+        }
+
+        index.artifacts.sort_by_key(|artifact| artifact.last_used);
+        let mut total: u64 = index.artifacts.iter().map(|a| a.size).sum();
+        while total > self.max_size {
+            let oldest = match index.artifacts.iter().position(|a| a.sha256 != sha256) {
+                Some(oldest) => index.artifacts.remove(oldest),
+                None => break,
+            };
+            let _ = fs::remove_file(self.artifact_path(&oldest.sha256));
+            total -= oldest.size;
+        }
+
+        self.write_index(&index)?;
+        Ok(path)
+    }
+
+    /// Removes every cached archive, returning how many bytes were freed
+    pub fn clear(&self) -> anyhow::Result<u64> {
+        let _guard = self.lock.lock().unwrap();
+        remove_dir(&self.dir)
+    }
+}
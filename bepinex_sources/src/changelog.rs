@@ -0,0 +1,84 @@
+use bepinex_helpers::game::Game;
+use semver::Version;
+
+use crate::{
+    bepinex::{BepInEx, BepInExRelease, ReleaseCommit, ReleaseFlavor},
+    version::cmp_precedence,
+};
+
+/// What changed between the installed BepInEx and the one about to be installed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changelog {
+    pub installed: Option<Version>,
+    /// Releases newer than the installed one up to and including the target, newest first.
+    /// Just the target when nothing is installed.
+    pub entries: Vec<BepInExRelease>,
+    /// The target is older than the installed version
+    pub downgrade: bool,
+}
+
+impl Changelog {
+    pub fn is_empty(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| entry.notes.is_none() && entry.commits.is_empty())
+    }
+
+    /// Every commit of the bleeding edge builds in the changelog, newest first
+    pub fn commits(&self) -> Vec<&ReleaseCommit> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.commits.iter())
+            .collect()
+    }
+}
+
+impl BepInEx {
+    /// Commits of the bleeding edge builds after `from` up to and including `to`, newest first
+    pub fn commits_between(&self, from: usize, to: usize) -> Vec<ReleaseCommit> {
+        let mut builds = self
+            .releases
+            .iter()
+            .filter(|r| r.flavor == ReleaseFlavor::BleedingEdge)
+            .filter_map(|r| r.artifact_id().map(|id| (id, r)))
+            .filter(|(id, _)| *id > from && *id <= to)
+            .collect::<Vec<_>>();
+        builds.sort_by(|(a, _), (b, _)| b.cmp(a));
+        builds.dedup_by_key(|(id, _)| *id);
+
+        builds
+            .into_iter()
+            .flat_map(|(_, r)| r.commits.iter().cloned())
+            .collect()
+    }
+
+    /// Releases of the target's flavor between the game's installed BepInEx and `target`
+    pub fn changelog(&self, game: &Game, target: &BepInExRelease) -> Changelog {
+        let installed = game.bepinex_version.clone();
+        let to = &target.version;
+
+        let downgrade = installed
+            .as_ref()
+            .map(|from| cmp_precedence(from, to).is_gt())
+            .unwrap_or_default();
+        let entries = match &installed {
+            Some(from) => self
+                .releases
+                .iter()
+                .filter(|r| r.flavor == target.flavor)
+                .filter(|r| {
+                    cmp_precedence(&r.version, from).is_gt()
+                        && cmp_precedence(&r.version, to).is_le()
+                })
+                .cloned()
+                .collect(),
+            None => vec![target.clone()],
+        };
+
+        Changelog {
+            installed,
+            entries,
+            downgrade,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{path::PathBuf, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "async")]
+use crate::transport::AsyncHttpResponse;
+use crate::transport::{
+    HttpRequest, HttpResponse, HttpTransport, Io, IoResponse, ReqwestTransport,
+};
+
+pub const DEFAULT_USER_AGENT: &str = concat!("bepinex_sources/", env!("CARGO_PKG_VERSION"));
+pub const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+pub const DEFAULT_BUILDS_URL: &str = "https://builds.bepinex.dev";
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 15;
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Network settings shared by every source and download. Everything is optional so it can be
+/// embedded as is in a settings file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    pub connect_timeout_secs: Option<u64>,
+    /// Longest wait for the response or the next part of its body. There's no limit on the
+    /// whole request, large downloads take as long as they need while data keeps coming.
+    pub timeout_secs: Option<u64>,
+    /// Used for every scheme, e.g. `http://proxy.corp:3128`
+    pub proxy: Option<String>,
+    /// PEM or DER files trusted next to the system roots
+    pub root_certificates: Vec<PathBuf>,
+    pub user_agent: Option<String>,
+    pub github_api_url: Option<String>,
+    pub builds_url: Option<String>,
+}
+
+/// Sources and downloads send their requests through this, over the network by default.
+/// Cheap to clone, clones share connections.
+#[derive(Debug, Clone)]
+pub struct SourcesClient {
+    config: ClientConfig,
+    transport: Arc<dyn HttpTransport>,
+}
+
+impl SourcesClient {
+    pub fn new(config: ClientConfig) -> anyhow::Result<Self> {
+        Ok(SourcesClient {
+            transport: Arc::new(ReqwestTransport::new(&config)?),
+            config,
+        })
+    }
+
+    /// Replaces the network, e.g. with a [`FixtureTransport`](crate::fixture::FixtureTransport)
+    pub fn set_transport(&mut self, transport: impl HttpTransport + 'static) -> &mut Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    pub fn transport(&self) -> &dyn HttpTransport {
+        self.transport.as_ref()
+    }
+
+    pub fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse> {
+        self.transport.send(request)
+    }
+
+    pub fn get(&self, url: &str) -> anyhow::Result<HttpResponse> {
+        self.send(HttpRequest::get(url))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, request: HttpRequest) -> anyhow::Result<AsyncHttpResponse> {
+        self.transport.send_async(request).await
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_async(&self, url: &str) -> anyhow::Result<AsyncHttpResponse> {
+        self.send_async(HttpRequest::get(url)).await
+    }
+
+    pub(crate) async fn send_with(
+        &self,
+        io: Io,
+        request: HttpRequest,
+    ) -> anyhow::Result<IoResponse> {
+        match io {
+            Io::Blocking => Ok(IoResponse::Blocking(self.send(request)?)),
+            #[cfg(feature = "async")]
+            Io::Async => Ok(IoResponse::Async(self.send_async(request).await?)),
+        }
+    }
+
+    pub(crate) async fn get_with(&self, io: Io, url: &str) -> anyhow::Result<IoResponse> {
+        self.send_with(io, HttpRequest::get(url)).await
+    }
+
+    pub fn github_api_url(&self) -> &str {
+        self.config
+            .github_api_url
+            .as_deref()
+            .unwrap_or(DEFAULT_GITHUB_API_URL)
+            .trim_end_matches('/')
+    }
+
+    pub fn builds_url(&self) -> &str {
+        self.config
+            .builds_url
+            .as_deref()
+            .unwrap_or(DEFAULT_BUILDS_URL)
+            .trim_end_matches('/')
+    }
+}
+
+impl Default for SourcesClient {
+    fn default() -> Self {
+        Self::new(ClientConfig::default()).expect("Failed to create HTTP client")
+    }
+}
@@ -0,0 +1,83 @@
+use bepinex_helpers::game::Game;
+
+use crate::{
+    bepinex::{BepInEx, BepInExRelease, ReleaseFlavor},
+    version::ReleaseRequirement,
+};
+
+impl BepInEx {
+    /// Supports the game's runtime, has an asset for its platform and arch and isn't known
+    /// to be broken
+    pub fn is_compatible(&self, release: &BepInExRelease, game: &Game) -> bool {
+        let supported_version = match release.flavor {
+            ReleaseFlavor::Stable => release.version >= self.rules.min_stable_version,
+            ReleaseFlavor::BleedingEdge => true,
+        };
+
+        supported_version
+            && self.rules.known_bad(release).is_none()
+            && self.rules.supports_runtime(release, game)
+            && release.select_asset(game).is_some()
+    }
+
+    /// Releases that can be installed into the game, newest first
+    pub fn compatible_releases(&self, game: &Game) -> Vec<&BepInExRelease> {
+        let mut releases = self
+            .releases
+            .iter()
+            .filter(|r| self.is_compatible(r, game))
+            .collect::<Vec<_>>();
+        releases.sort_by(|a, b| b.cmp_version(a));
+        releases
+    }
+
+    /// Newest compatible release of the flavor, preferring stable versions over pre-releases
+    pub fn latest_for(&self, game: &Game, flavor: ReleaseFlavor) -> Option<BepInExRelease> {
+        let releases = self
+            .compatible_releases(game)
+            .into_iter()
+            .filter(|r| r.flavor == flavor)
+            .collect::<Vec<_>>();
+
+        releases
+            .iter()
+            .find(|r| r.version.pre.is_empty())
+            .or_else(|| releases.first())
+            .map(|r| (*r).to_owned())
+    }
+
+    /// Release recommended for the game by the rules, or the latest one when there's none
+    pub fn recommended_for(&self, game: &Game, flavor: ReleaseFlavor) -> Option<BepInExRelease> {
+        let recommended = self.rules.recommended(game);
+        self.compatible_releases(game)
+            .into_iter()
+            .filter(|r| r.flavor == flavor)
+            .find(|r| recommended.iter().any(|rec| rec.release.matches(r)))
+            .cloned()
+            .or_else(|| self.latest_for(game, flavor))
+    }
+
+    /// Releases matching the requirement, newest first
+    pub fn matching(&self, req: &ReleaseRequirement) -> Vec<&BepInExRelease> {
+        let mut releases = self
+            .releases
+            .iter()
+            .filter(|r| req.matches(r))
+            .collect::<Vec<_>>();
+        releases.sort_by(|a, b| b.cmp_version(a));
+        releases
+    }
+
+    /// Newest release matching the requirement, limited to ones compatible with the game if
+    /// one is given
+    pub fn latest_matching(
+        &self,
+        req: &ReleaseRequirement,
+        game: Option<&Game>,
+    ) -> Option<BepInExRelease> {
+        self.matching(req)
+            .into_iter()
+            .find(|r| game.map(|game| self.is_compatible(r, game)).unwrap_or(true))
+            .cloned()
+    }
+}
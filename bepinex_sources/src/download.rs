@@ -0,0 +1,444 @@
+use std::{
+    env, error,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use bepinex_helpers::game::Game;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
+use zip::ZipArchive;
+
+use crate::{
+    bepinex::BepInExAsset,
+    cache::ArtifactCache,
+    client::SourcesClient,
+    extract::{self, ExtractionPlan},
+    hash::{sha256_file, sha256_hex},
+    transport::{block_on, HttpRequest, Io},
+};
+
+/// Progress isn't reported more often than this
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub downloaded: u64,
+    /// From `Content-Length` or the size the source gave
+    pub total: Option<u64>,
+    /// Average since the download (re)started
+    pub bytes_per_sec: f64,
+}
+
+impl Progress {
+    /// Between 0 and 1, `None` if the total size isn't known
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64).min(1.0) as f32)
+    }
+}
+
+/// Cancels a download from another thread. Clones cancel the same download.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The download was cancelled through its [`CancelToken`]. What was downloaded so far is
+/// kept and resumed next time.
+#[derive(Debug, Clone, Copy)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Download cancelled")
+    }
+}
+
+impl error::Error for Cancelled {}
+
+/// Archive on disk, removed on drop if it was only downloaded into the temp dir
+#[derive(Debug)]
+pub struct DownloadedArchive {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl DownloadedArchive {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn open(&self) -> std::io::Result<File> {
+        File::open(&self.path)
+    }
+
+    /// Extracts the archive into the game, returning the files written
+    pub fn extract(&self, game: &Game) -> anyhow::Result<ExtractionPlan> {
+        Ok(extract::extract(&mut ZipArchive::new(self.open()?)?, game)?)
+    }
+}
+
+impl Drop for DownloadedArchive {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Streams assets to disk, resuming partial downloads with HTTP `Range` requests
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: SourcesClient,
+    cache: Option<ArtifactCache>,
+    /// Partial downloads are kept here until they're finished
+    temp_dir: PathBuf,
+    progress: Option<Sender<Progress>>,
+    cancel: CancelToken,
+}
+
+impl Downloader {
+    pub fn new(client: SourcesClient) -> Self {
+        Downloader {
+            client,
+            cache: None,
+            temp_dir: env::temp_dir().join("bepinex_sources"),
+            progress: None,
+            cancel: CancelToken::default(),
+        }
+    }
+
+    pub fn set_cache(&mut self, cache: Option<ArtifactCache>) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn set_temp_dir(&mut self, temp_dir: impl Into<PathBuf>) -> &mut Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Progress is sent here, the send fails silently once the receiver is gone
+    pub fn set_progress(&mut self, progress: Option<Sender<Progress>>) -> &mut Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn set_cancel_token(&mut self, cancel: CancelToken) -> &mut Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    fn report(&self, progress: Progress) {
+        if let Some(sender) = &self.progress {
+            let _ = sender.send(progress);
+        }
+    }
+
+    /// Whether [`download`](Downloader::download) can do without the network
+    pub fn is_cached(&self, asset: &BepInExAsset) -> bool {
+        asset.link.starts_with("file://")
+            || self
+                .cache
+                .as_ref()
+                .map(|cache| cache.get_path(asset).is_some())
+                .unwrap_or(false)
+    }
+
+    /// Local archive for `file://` links, the cached one if there is one, or a fresh
+    /// download otherwise. Downloads are checked against the asset's SHA-256.
+    pub fn download(&self, asset: &BepInExAsset) -> anyhow::Result<DownloadedArchive> {
+        block_on(self.download_with(Io::Blocking, asset))
+    }
+
+    /// Async [`download`](Downloader::download). Hashing and writing run on tokio's
+    /// blocking threads.
+    #[cfg(feature = "async")]
+    pub async fn download_async(&self, asset: &BepInExAsset) -> anyhow::Result<DownloadedArchive> {
+        self.download_with(Io::Async, asset).await
+    }
+
+    pub(crate) async fn download_with(
+        &self,
+        io: Io,
+        asset: &BepInExAsset,
+    ) -> anyhow::Result<DownloadedArchive> {
+        let local = self.blocking(io, asset, |d, asset| d.local(asset));
+        if let Some(archive) = local.await? {
+            return Ok(archive);
+        }
+        let part = self.part_path(asset);
+        self.stream(io, asset, &part).await?;
+        self.blocking(io, asset, move |d, asset| d.finish(asset, part))
+            .await
+    }
+
+    async fn blocking<T, F>(&self, io: Io, asset: &BepInExAsset, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Downloader, &BepInExAsset) -> anyhow::Result<T> + Send + 'static,
+    {
+        let (downloader, asset) = (self.clone(), asset.clone());
+        io.run_blocking(move || f(&downloader, &asset)).await
+    }
+
+    /// The archive if it's already on disk
+    fn local(&self, asset: &BepInExAsset) -> anyhow::Result<Option<DownloadedArchive>> {
+        if asset.link.starts_with("file://") {
+            let path = asset.file_path()?;
+            asset.verify_sha256(&sha256_file(&path)?)?;
+            return Ok(Some(DownloadedArchive {
+                path,
+                temporary: false,
+            }));
+        }
+
+        match self.cache.as_ref().and_then(|cache| cache.get_path(asset)) {
+            Some(path) => {
+                let size = fs::metadata(&path)?.len();
+                self.report(Progress {
+                    downloaded: size,
+                    total: Some(size),
+                    bytes_per_sec: 0.0,
+                });
+                Ok(Some(DownloadedArchive {
+                    path,
+                    temporary: false,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Where the asset is downloaded to until it's complete. The `ETag` or `Last-Modified`
+    /// of the response is kept next to it in a `.validator` file, resuming without one
+    /// could stitch together two different files.
+    pub fn part_path(&self, asset: &BepInExAsset) -> PathBuf {
+        let id = format!(
+            "{}\n{}\n{}",
+            asset.link,
+            asset.size.unwrap_or_default(),
+            asset.sha256.as_deref().unwrap_or_default()
+        );
+        let key = &sha256_hex(id.as_bytes())[..16];
+        self.temp_dir.join(format!("{}.part", key))
+    }
+
+    /// Checks the finished download and moves it into the cache if there is one
+    fn finish(&self, asset: &BepInExAsset, part: PathBuf) -> anyhow::Result<DownloadedArchive> {
+        let _ = fs::remove_file(validator_path(&part));
+        let sha256 = sha256_file(&part)?;
+        if let Err(e) = asset.verify_sha256(&sha256) {
+            let _ = fs::remove_file(&part);
+            return Err(e.into());
+        }
+        let path = part.with_extension("zip");
+        fs::rename(&part, &path)?;
+
+        let archive = DownloadedArchive {
+            path,
+            temporary: true,
+        };
+        // The download is still usable if it can't be cached
+        match self
+            .cache
+            .as_ref()
+            .map(|cache| cache.put_file(asset, archive.path()))
+        {
+            Some(Ok(path)) => Ok(DownloadedArchive {
+                path,
+                temporary: false,
+            }),
+            _ => Ok(archive),
+        }
+    }
+
+    /// Appends to `part` from where a previous attempt stopped
+    async fn stream(&self, io: Io, asset: &BepInExAsset, part: &Path) -> anyhow::Result<()> {
+        let (mut offset, validator) = partial(part)?;
+        if self.cancel.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+
+        let request = range_request(asset, offset, validator.as_deref())?;
+        let mut resp = self.client.send_with(io, request).await?;
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file is complete or doesn't belong to this asset anymore
+            offset = 0;
+            resp = self.client.get_with(io, &asset.link).await?;
+        }
+        let mut resp = resp.error_for_status()?;
+
+        let append = resumes(offset, resp.status());
+        if !append {
+            offset = 0;
+        }
+        save_validator(part, resp.headers())?;
+        let total = resp.content_length().map(|len| len + offset).or(asset.size);
+        let path = part.to_owned();
+        let mut file = io
+            .run_blocking(move || {
+                Ok(OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(append)
+                    .truncate(!append)
+                    .open(path)?)
+            })
+            .await?;
+
+        let mut transfer = Transfer::new(offset, total);
+        loop {
+            if self.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+
+            let chunk = match resp.chunk().await? {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let read = chunk.len();
+            file = io
+                .run_blocking(move || {
+                    file.write_all(&chunk)?;
+                    Ok(file)
+                })
+                .await?;
+            if let Some(progress) = transfer.advance(read) {
+                self.report(progress);
+            }
+        }
+        io.run_blocking(move || Ok(file.flush()?)).await?;
+
+        self.report(transfer.finish());
+        Ok(())
+    }
+}
+
+fn validator_path(part: &Path) -> PathBuf {
+    part.with_extension("validator")
+}
+
+/// Size of what a previous attempt downloaded and the validator of the response it came
+/// from. Partial files without a validator can't be resumed safely and start over.
+fn partial(part: &Path) -> anyhow::Result<(u64, Option<String>)> {
+    if let Some(dir) = part.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let validator = fs::read_to_string(validator_path(part))
+        .ok()
+        .filter(|validator| !validator.is_empty());
+    let offset = match validator {
+        Some(_) => fs::metadata(part).map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+    Ok((offset, validator))
+}
+
+/// Keeps the strong `ETag` or the `Last-Modified` date of the response for resuming,
+/// weak `ETag`s can't be used with `If-Range`
+fn save_validator(part: &Path, headers: &HeaderMap) -> anyhow::Result<()> {
+    let validator = headers
+        .get(header::ETAG)
+        .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+        .or_else(|| headers.get(header::LAST_MODIFIED))
+        .and_then(|value| value.to_str().ok());
+    match validator {
+        Some(validator) => fs::write(validator_path(part), validator)?,
+        None => {
+            let _ = fs::remove_file(validator_path(part));
+        }
+    }
+    Ok(())
+}
+
+/// Asks for the rest of the asset after `offset` bytes, as long as it's still the same file
+fn range_request(
+    asset: &BepInExAsset,
+    offset: u64,
+    validator: Option<&str>,
+) -> anyhow::Result<HttpRequest> {
+    let mut request = HttpRequest::get(&asset.link);
+    if let Some(validator) = validator.filter(|_| offset > 0) {
+        let range = HeaderValue::from_str(&format!("bytes={}-", offset))?;
+        request.headers.insert(header::RANGE, range);
+        request
+            .headers
+            .insert(header::IF_RANGE, HeaderValue::from_str(validator)?);
+    }
+    Ok(request)
+}
+
+/// Servers that ignore `Range` send the whole file again
+fn resumes(offset: u64, status: StatusCode) -> bool {
+    offset > 0 && status == StatusCode::PARTIAL_CONTENT
+}
+
+/// Bytes written so far, reported at most every [`PROGRESS_INTERVAL`]
+struct Transfer {
+    started: Instant,
+    reported: Instant,
+    /// Already on disk when the transfer started
+    offset: u64,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+impl Transfer {
+    fn new(offset: u64, total: Option<u64>) -> Self {
+        Transfer {
+            started: Instant::now(),
+            reported: Instant::now(),
+            offset,
+            downloaded: offset,
+            total,
+        }
+    }
+
+    /// `Some` once it's time to report progress again
+    fn advance(&mut self, read: usize) -> Option<Progress> {
+        self.downloaded += read as u64;
+        if self.reported.elapsed() < PROGRESS_INTERVAL {
+            return None;
+        }
+        self.reported = Instant::now();
+        Some(self.progress(self.total))
+    }
+
+    fn finish(&self) -> Progress {
+        self.progress(Some(self.downloaded))
+    }
+
+    fn progress(&self, total: Option<u64>) -> Progress {
+        Progress {
+            downloaded: self.downloaded,
+            total,
+            bytes_per_sec: (self.downloaded - self.offset) as f64
+                / self.started.elapsed().as_secs_f64().max(0.001),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    error,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Read, Seek},
+    path::{Component, Path, PathBuf},
+};
+
+use bepinex_helpers::game::{Game, GameType};
+use zip::{result::ZipError, ZipArchive};
+
+use crate::asset::{AssetArch, AssetRuntime};
+
+/// Doorstop proxies that load BepInEx, one of them has to be in the archive root
+pub const DOORSTOP_PROXIES: [&str; 3] = ["winhttp.dll", "version.dll", "run_bepinex.sh"];
+pub(crate) const CORE_DIR: &str = "BepInEx/core";
+/// Existing files in here are never overwritten
+const CONFIG_DIR: &str = "BepInEx/config";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+#[derive(Debug)]
+pub enum ExtractError {
+    /// Absolute path or one with `..` that would end up outside the game folder
+    UnsafePath(String),
+    /// Symlink pointing outside the game folder
+    UnsafeSymlink {
+        path: String,
+        target: String,
+    },
+    MissingCore,
+    MissingDoorstop,
+    RuntimeMismatch {
+        game: GameType,
+        archive: AssetRuntime,
+    },
+    ArchMismatch {
+        game: String,
+        archive: AssetArch,
+    },
+    Zip(ZipError),
+    Io(io::Error),
+}
+
+impl Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::UnsafePath(path) => {
+                write!(f, "Archive entry {} points outside the game folder", path)
+            }
+            ExtractError::UnsafeSymlink { path, target } => write!(
+                f,
+                "Archive symlink {} -> {} points outside the game folder",
+                path, target
+            ),
+            ExtractError::MissingCore => write!(f, "Archive has no {} folder", CORE_DIR),
+            ExtractError::MissingDoorstop => write!(
+                f,
+                "Archive has no doorstop proxy ({})",
+                DOORSTOP_PROXIES.join(", ")
+            ),
+            ExtractError::RuntimeMismatch { game, archive } => write!(
+                f,
+                "Archive is built for {} but the game is {}",
+                archive, game
+            ),
+            ExtractError::ArchMismatch { game, archive } => write!(
+                f,
+                "Archive is built for {} but the game is {}",
+                archive, game
+            ),
+            ExtractError::Zip(e) => write!(f, "Invalid archive: {}", e),
+            ExtractError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for ExtractError {}
+
+impl From<ZipError> for ExtractError {
+    fn from(e: ZipError) -> Self {
+        ExtractError::Zip(e)
+    }
+}
+
+impl From<io::Error> for ExtractError {
+    fn from(e: io::Error) -> Self {
+        ExtractError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// Relative to the game folder
+    pub path: PathBuf,
+    pub size: u64,
+    /// A file already exists there
+    pub overwrites: bool,
+}
+
+/// What extracting an archive into a game writes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractionPlan {
+    pub files: Vec<PlannedFile>,
+    /// Files in `BepInEx/core` the archive doesn't have, left over from another version
+    pub stale: Vec<PathBuf>,
+    /// Config files already in the game, kept even if the archive has them
+    pub preserved: Vec<PathBuf>,
+    /// Runtime of the files in `BepInEx/core`
+    pub runtime: Option<AssetRuntime>,
+    /// Arch of the Windows doorstop proxy
+    pub arch: Option<AssetArch>,
+}
+
+impl ExtractionPlan {
+    pub fn overwritten(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().filter(|file| file.overwrites)
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// Files under `dir` of the game folder, relative to the game folder
+fn files_under(root: &Path, dir: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::from(dir)];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(root.join(&dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = dir.join(entry.file_name());
+            match entry.file_type() {
+                Ok(ty) if ty.is_dir() => dirs.push(path),
+                Ok(_) => files.push(path),
+                Err(_) => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Lexically resolves `..` and `.`, `None` if the path leaves its root
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// Runtime a `BepInEx/core` assembly is built for, judged by its lowercase file name
+pub(crate) fn core_runtime(file_name: &str) -> Option<AssetRuntime> {
+    match file_name.ends_with(".dll") {
+        true if file_name.contains("il2cpp") => Some(AssetRuntime::UnityIL2CPP),
+        true => Some(AssetRuntime::UnityMono),
+        false => None,
+    }
+}
+
+/// Machine field of a PE header
+pub(crate) fn pe_arch(bytes: &[u8]) -> Option<AssetArch> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let pe = u32_at(0x3C)? as usize;
+    if bytes.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+    match u16::from_le_bytes(bytes.get(pe + 4..pe + 6)?.try_into().ok()?) {
+        0x014C => Some(AssetArch::X86),
+        0x8664 => Some(AssetArch::X64),
+        0xAA64 => Some(AssetArch::Arm64),
+        _ => None,
+    }
+}
+
+fn is_symlink(mode: Option<u32>) -> bool {
+    mode.map(|mode| mode & S_IFMT == S_IFLNK).unwrap_or(false)
+}
+
+/// Checks every entry and the BepInEx layout without writing anything. Fails on paths
+/// leaving the game folder, a missing `BepInEx/core` or doorstop proxy, and archives built
+/// for another runtime or arch than the game's.
+pub fn plan<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    game: &Game,
+) -> Result<ExtractionPlan, ExtractError> {
+    let mut plan = ExtractionPlan::default();
+    let mut has_core = false;
+    let mut has_doorstop = false;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let path = file
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| ExtractError::UnsafePath(file.name().to_owned()))?;
+        if file.is_dir() {
+            continue;
+        }
+
+        if is_symlink(file.unix_mode()) {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            let parent = path.parent().unwrap_or_else(|| Path::new(""));
+            if normalize(&parent.join(&target)).is_none() {
+                return Err(ExtractError::UnsafeSymlink {
+                    path: path.display().to_string(),
+                    target,
+                });
+            }
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if path.starts_with(CORE_DIR) {
+            has_core = true;
+            // A single IL2CPP assembly makes it an IL2CPP build
+            if plan.runtime != Some(AssetRuntime::UnityIL2CPP) {
+                plan.runtime = core_runtime(&file_name).or(plan.runtime);
+            }
+        }
+        if path.parent() == Some(Path::new("")) && DOORSTOP_PROXIES.contains(&file_name.as_str()) {
+            has_doorstop = true;
+            if file_name.ends_with(".dll") {
+                let mut bytes = Vec::new();
+                (&mut file).take(4096).read_to_end(&mut bytes)?;
+                plan.arch = pe_arch(&bytes);
+            }
+        }
+
+        plan.files.push(PlannedFile {
+            overwrites: game.path.join(&path).exists(),
+            size: file.size(),
+            path,
+        });
+    }
+
+    if !has_core {
+        return Err(ExtractError::MissingCore);
+    }
+    if !has_doorstop {
+        return Err(ExtractError::MissingDoorstop);
+    }
+    match (&game.ty, plan.runtime) {
+        (Some(ty), Some(runtime)) if AssetRuntime::from(ty) != runtime => {
+            return Err(ExtractError::RuntimeMismatch {
+                game: ty.clone(),
+                archive: runtime,
+            })
+        }
+        _ => {}
+    }
+    match plan.arch {
+        Some(arch) if !game.arch.is_empty() && arch.to_string() != game.arch => {
+            return Err(ExtractError::ArchMismatch {
+                game: game.arch.clone(),
+                archive: arch,
+            })
+        }
+        _ => {}
+    }
+
+    plan.preserved = files_under(&game.path, CONFIG_DIR);
+    let preserved = &plan.preserved;
+    plan.files.retain(|file| !preserved.contains(&file.path));
+    let archived = &plan.files;
+    plan.stale = files_under(&game.path, CORE_DIR)
+        .into_iter()
+        .filter(|path| !archived.iter().any(|file| file.path == *path))
+        .collect();
+
+    Ok(plan)
+}
+
+/// Extracts into the game folder after [`plan`] accepted the archive. Files the archive
+/// doesn't have are left alone, including the stale core files the plan lists, since callers
+/// may not have shown those to the user. [`remove_stale`] deletes them once they agreed.
+pub fn extract<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    game: &Game,
+) -> Result<ExtractionPlan, ExtractError> {
+    let plan = plan(archive, game)?;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let path = match file.enclosed_name() {
+            Some(path) if plan.preserved.iter().any(|kept| kept == path) => continue,
+            Some(path) => game.path.join(path),
+            None => continue,
+        };
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        #[cfg(unix)]
+        if is_symlink(file.unix_mode()) {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            if path.symlink_metadata().is_ok() {
+                fs::remove_file(&path)?;
+            }
+            std::os::unix::fs::symlink(target, &path)?;
+            continue;
+        }
+
+        io::copy(&mut file, &mut File::create(&path)?)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Deletes the stale core files of a plan from the game folder. Anything outside
+/// `BepInEx/core` is skipped, and files that are already gone aren't an error.
+pub fn remove_stale(game_path: &Path, stale: &[PathBuf]) -> io::Result<()> {
+    for path in stale.iter() {
+        let path = match normalize(path) {
+            Some(path) if path.starts_with(CORE_DIR) => path,
+            _ => continue,
+        };
+        match fs::remove_file(game_path.join(path)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::Cursor,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "async")]
+use futures_util::future::BoxFuture;
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    StatusCode, Url,
+};
+
+#[cfg(feature = "async")]
+use crate::transport::AsyncHttpResponse;
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+
+#[derive(Debug, Clone)]
+pub enum FixtureBody {
+    Bytes(Vec<u8>),
+    /// Read when the request is made
+    File(PathBuf),
+}
+
+/// Recorded response served by a [`FixtureTransport`]
+#[derive(Debug, Clone)]
+pub struct FixtureResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: FixtureBody,
+}
+
+impl FixtureResponse {
+    pub fn bytes(body: impl Into<Vec<u8>>) -> Self {
+        FixtureResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: FixtureBody::Bytes(body.into()),
+        }
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        FixtureResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: FixtureBody::File(path.into()),
+        }
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) -> &mut Self {
+        self.status = status;
+        self
+    }
+
+    /// Ignored if `value` isn't a valid header value
+    pub fn set_header(&mut self, name: HeaderName, value: &str) -> &mut Self {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+/// Serves recorded responses instead of going over the network, so whole fetch and install
+/// runs work offline. Requests nothing was recorded for fail.
+///
+/// ```
+/// # use bepinex_sources::{client::SourcesClient, fixture::{FixtureResponse, FixtureTransport}};
+/// let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+/// let mut transport = FixtureTransport::default();
+/// transport
+///     .add(
+///         "https://api.github.com/repos/BepInEx/BepInEx/releases",
+///         FixtureResponse::file(format!("{}/github/releases.json", fixtures)),
+///     )
+///     .add_dir("https://github.com/BepInEx/BepInEx/releases/download", fixtures);
+/// let mut client = SourcesClient::default();
+/// client.set_transport(transport);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FixtureTransport {
+    responses: HashMap<String, FixtureResponse>,
+    /// Base URLs served from directories
+    dirs: Vec<(String, PathBuf)>,
+    /// Shared between clones
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl FixtureTransport {
+    /// Response for `url`. The query string is ignored unless `url` has one.
+    pub fn add(&mut self, url: &str, response: FixtureResponse) -> &mut Self {
+        self.responses.insert(url.to_owned(), response);
+        self
+    }
+
+    /// Serves files under `dir` at the same path under `base_url`, handy for archives.
+    /// Relative paths are resolved against the current directory right away.
+    pub fn add_dir(&mut self, base_url: &str, dir: impl Into<PathBuf>) -> &mut Self {
+        let dir = dir.into();
+        // File URLs can only be built from absolute paths, and `find` compares against the
+        // resolved path
+        let dir = fs::canonicalize(&dir).unwrap_or_else(|_| match env::current_dir() {
+            Ok(cwd) => cwd.join(dir),
+            Err(_) => dir,
+        });
+        self.dirs
+            .push((base_url.trim_end_matches('/').to_owned(), dir));
+        self
+    }
+
+    /// Every request sent so far, oldest first
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn find(&self, url: &str) -> Option<FixtureResponse> {
+        let without_query = url.split('?').next().unwrap_or(url);
+        if let Some(response) = self
+            .responses
+            .get(url)
+            .or_else(|| self.responses.get(without_query))
+        {
+            return Some(response.clone());
+        }
+
+        self.dirs.iter().find_map(|(base_url, dir)| {
+            let relative = without_query.strip_prefix(base_url.as_str())?;
+            // Joining a file URL decodes escapes like `%2B` in the file name
+            let path = Url::from_directory_path(dir)
+                .ok()?
+                .join(relative.trim_start_matches('/'))
+                .ok()?
+                .to_file_path()
+                .ok()?;
+            (path.starts_with(dir) && path.is_file()).then(|| FixtureResponse::file(path))
+        })
+    }
+
+    /// Status, headers and body, honoring `Range: bytes=<start>-` so resumed downloads work.
+    /// `If-Range` has to match the fixture's `ETag` or `Last-Modified` header for the range
+    /// to apply.
+    fn respond(&self, request: HttpRequest) -> anyhow::Result<(StatusCode, HeaderMap, Vec<u8>)> {
+        self.requests.lock().unwrap().push(request.clone());
+        let fixture = self
+            .find(&request.url)
+            .ok_or_else(|| anyhow::anyhow!("No fixture for {} {}", request.method, request.url))?;
+
+        let mut body = match fixture.body {
+            FixtureBody::Bytes(bytes) => bytes,
+            FixtureBody::File(path) => fs::read(path)?,
+        };
+        let (mut status, mut headers) = (fixture.status, fixture.headers);
+        let start = request
+            .headers
+            .get(header::RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .and_then(|start| start.parse::<usize>().ok())
+            .filter(|_| match request.headers.get(header::IF_RANGE) {
+                Some(validator) => [header::ETAG, header::LAST_MODIFIED]
+                    .iter()
+                    .any(|name| headers.get(name) == Some(validator)),
+                None => true,
+            });
+        match start {
+            Some(start) if status == StatusCode::OK && start >= body.len() => {
+                status = StatusCode::RANGE_NOT_SATISFIABLE;
+                body = Vec::new();
+            }
+            Some(start) if status == StatusCode::OK => {
+                let range = format!("bytes {}-{}/{}", start, body.len() - 1, body.len());
+                headers.insert(header::CONTENT_RANGE, HeaderValue::from_str(&range)?);
+                status = StatusCode::PARTIAL_CONTENT;
+                body.drain(..start);
+            }
+            _ => {}
+        }
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+        Ok((status, headers, body))
+    }
+}
+
+impl HttpTransport for FixtureTransport {
+    fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse> {
+        let url = request.url.clone();
+        let (status, headers, body) = self.respond(request)?;
+        Ok(HttpResponse::new(url, status, headers, Cursor::new(body)))
+    }
+
+    #[cfg(feature = "async")]
+    fn send_async(&self, request: HttpRequest) -> BoxFuture<'_, anyhow::Result<AsyncHttpResponse>> {
+        Box::pin(async move {
+            let url = request.url.clone();
+            let (status, headers, body) = self.respond(request)?;
+            Ok(AsyncHttpResponse::from_bytes(url, status, headers, body))
+        })
+    }
+}
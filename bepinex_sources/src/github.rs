@@ -1,13 +1,155 @@
-use reqwest::header::{self, HeaderMap};
+use std::{
+    collections::HashMap,
+    env, error,
+    fmt::{Debug, Display},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "async")]
+use futures_util::future::BoxFuture;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    StatusCode,
+};
 use semver::Version;
+use serde::Deserialize;
+
+use crate::{
+    bepinex::BepInExRelease,
+    client::SourcesClient,
+    hash::sha256_hex,
+    models::github::releases::GitHubRelease,
+    source::{ReleaseSource, SourceReleases},
+    transport::{block_on, HttpRequest, Io},
+};
+
+pub const SOURCE_ID: &str = "github";
+
+/// Environment variable the token is read from by default
+pub const TOKEN_ENV_VAR: &str = "GITHUB_TOKEN";
+
+#[derive(Debug)]
+pub enum GitHubError {
+    /// Out of requests until `reset`. Anonymous requests are limited to 60 an hour per IP.
+    RateLimited {
+        limit: Option<u64>,
+        reset: Option<SystemTime>,
+        authenticated: bool,
+    },
+    Status {
+        status: StatusCode,
+        message: String,
+    },
+}
+
+impl Display for GitHubError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubError::RateLimited {
+                limit,
+                reset,
+                authenticated,
+            } => {
+                write!(f, "GitHub API rate limit")?;
+                if let Some(limit) = limit {
+                    write!(f, " of {} requests", limit)?;
+                }
+                write!(f, " exceeded")?;
+                if let Some(reset) = reset {
+                    let wait = reset
+                        .duration_since(SystemTime::now())
+                        .unwrap_or_default()
+                        .as_secs();
+                    let minutes = wait / 60 + u64::from(wait % 60 != 0);
+                    write!(f, ", resets in {} min", minutes)?;
+                }
+                if !authenticated {
+                    write!(f, ", set {} to raise the limit", TOKEN_ENV_VAR)?;
+                }
+                Ok(())
+            }
+            GitHubError::Status { status, message } if message.is_empty() => {
+                write!(f, "GitHub API returned {}", status)
+            }
+            GitHubError::Status { status, message } => {
+                write!(f, "GitHub API returned {}: {}", status, message)
+            }
+        }
+    }
+}
+
+impl error::Error for GitHubError {}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// URL of the `rel="next"` entry of a `Link` header:
+/// `<https://api.github.com/…&page=2>; rel="next", <https://api.github.com/…&page=5>; rel="last"`
+pub fn next_link(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::LINK)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .find_map(|link| {
+            let (url, params) = link.split_once(';')?;
+            params
+                .split(';')
+                .any(|param| param.trim() == r#"rel="next""#)
+                .then(|| {
+                    url.trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_owned()
+                })
+        })
+}
+
+/// One page of releases, kept with its ETag so it can be revalidated
+#[derive(Debug, Clone)]
+pub struct ReleasesPage {
+    pub releases: Vec<GitHubRelease>,
+    pub next: Option<String>,
+    pub etag: Option<String>,
+}
 
-use crate::models::github::releases::GitHubRelease;
+/// Pages by URL. Shared between clones of a [`GitHubApi`], so repeat fetches only cost
+/// `304 Not Modified` responses, which don't count against the rate limit.
+pub type PageCache = Arc<Mutex<HashMap<String, ReleasesPage>>>;
 
 pub struct GitHubApi {
     owner: String,
     repo: String,
     pre_releases: bool,
     min_tag: Option<Version>,
+    client: SourcesClient,
+    token: Option<String>,
+    /// Overrides the client's API URL, e.g. `https://github.example.com/api/v3`
+    base_url: Option<String>,
+    pages: PageCache,
+}
+
+/// Leaves out the token, sources end up in logs through [`BepInEx`](crate::bepinex::BepInEx)
+impl Debug for GitHubApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitHubApi")
+            .field("owner", &self.owner)
+            .field("repo", &self.repo)
+            .field("pre_releases", &self.pre_releases)
+            .field("min_tag", &self.min_tag)
+            .field("client", &self.client)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("base_url", &self.base_url)
+            .field("pages", &self.pages)
+            .finish()
+    }
 }
 
 impl GitHubApi {
@@ -17,9 +159,43 @@ impl GitHubApi {
             repo: repo.into(),
             pre_releases: false,
             min_tag: None,
+            client: SourcesClient::default(),
+            token: env::var(TOKEN_ENV_VAR)
+                .ok()
+                .filter(|token| !token.is_empty()),
+            base_url: None,
+            pages: PageCache::default(),
         }
     }
 
+    pub fn set_page_cache(&mut self, pages: PageCache) -> &mut Self {
+        self.pages = pages;
+        self
+    }
+
+    /// Replaces the token read from `GITHUB_TOKEN`
+    pub fn set_token(&mut self, token: Option<String>) -> &mut Self {
+        self.token = token;
+        self
+    }
+
+    pub fn set_base_url(&mut self, base_url: Option<String>) -> &mut Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .map(|url| url.trim_end_matches('/'))
+            .unwrap_or_else(|| self.client.github_api_url())
+    }
+
+    pub fn set_client(&mut self, client: SourcesClient) -> &mut Self {
+        self.client = client;
+        self
+    }
+
     pub fn set_pre_releases(&mut self, pre_releases: bool) -> &mut Self {
         self.pre_releases = pre_releases;
         self
@@ -31,6 +207,10 @@ impl GitHubApi {
     }
 
     pub fn filter_release(&self, release: &GitHubRelease) -> bool {
+        if release.draft {
+            return false;
+        }
+
         // if pres enabled, return all releases
         if !self.pre_releases && release.pre_release {
             return false;
@@ -42,44 +222,204 @@ impl GitHubApi {
         }
     }
 
+    fn releases_url(&self, per_page: u32, page: u32) -> String {
+        format!(
+            "{}/repos/{}/{}/releases?per_page={}&page={}",
+            self.base_url(),
+            self.owner,
+            self.repo,
+            per_page,
+            page
+        )
+    }
+
     pub fn get_releases(&self, per_page: u32, page: u32) -> anyhow::Result<Vec<GitHubRelease>> {
-        let api_url = format!(
-            "https://api.github.com/repos/{}/{}/releases?per_page={}&page={}",
-            self.repo, self.owner, per_page, page
-        );
+        Ok(self.get_page(&self.releases_url(per_page, page))?.releases)
+    }
+
+    /// Fetches a page, revalidating it with `If-None-Match` if it was fetched before
+    pub fn get_page(&self, url: &str) -> anyhow::Result<ReleasesPage> {
+        block_on(self.get_page_with(Io::Blocking, url))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_releases_async(
+        &self,
+        per_page: u32,
+        page: u32,
+    ) -> anyhow::Result<Vec<GitHubRelease>> {
+        let url = self.releases_url(per_page, page);
+        Ok(self.get_page_async(&url).await?.releases)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_page_async(&self, url: &str) -> anyhow::Result<ReleasesPage> {
+        self.get_page_with(Io::Async, url).await
+    }
+
+    async fn get_page_with(&self, io: Io, url: &str) -> anyhow::Result<ReleasesPage> {
+        let cached = self.cached_page(url);
+        let request = HttpRequest {
+            headers: self.request_headers(cached.as_ref()),
+            ..HttpRequest::get(url)
+        };
+        let resp = self.client.send_with(io, request).await?;
+        let (status, headers) = (resp.status(), resp.headers().clone());
+        self.read_page(url, cached, status, &headers, &resp.bytes().await?)
+    }
+
+    fn cached_page(&self, url: &str) -> Option<ReleasesPage> {
+        self.pages.lock().unwrap().get(url).cloned()
+    }
 
+    fn request_headers(&self, cached: Option<&ReleasesPage>) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert(
-            header::USER_AGENT,
-            "rust-reqwest/gh-api".parse().expect("Invalid UA"),
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
         );
+        let token = self.token.as_ref().and_then(|token| {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token)).ok()?;
+            value.set_sensitive(true);
+            Some(value)
+        });
+        if let Some(token) = token {
+            headers.insert(header::AUTHORIZATION, token);
+        }
+        let etag = cached.and_then(|page| page.etag.as_deref());
+        if let Some(etag) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+            headers.insert(header::IF_NONE_MATCH, etag);
+        }
+        headers
+    }
 
-        let resp = reqwest::blocking::Client::new()
-            .get(&api_url)
-            .headers(headers)
-            .send()?;
-        let releases = resp.json::<Vec<GitHubRelease>>()?;
+    /// The cached page on `304 Not Modified`, the parsed body otherwise
+    fn read_page(
+        &self,
+        url: &str,
+        cached: Option<ReleasesPage>,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> anyhow::Result<ReleasesPage> {
+        if let (StatusCode::NOT_MODIFIED, Some(page)) = (status, cached) {
+            return Ok(page);
+        }
+        if !status.is_success() {
+            return Err(self.response_error(status, headers, body).into());
+        }
 
-        Ok(releases)
+        let page = ReleasesPage {
+            releases: serde_json::from_slice::<Vec<GitHubRelease>>(body)?,
+            next: next_link(headers),
+            etag: headers
+                .get(header::ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(str::to_owned),
+        };
+        if page.etag.is_some() {
+            self.pages
+                .lock()
+                .unwrap()
+                .insert(url.to_owned(), page.clone());
+        }
+        Ok(page)
     }
 
+    /// [`GitHubError`] of an error response
+    fn response_error(&self, status: StatusCode, headers: &HeaderMap, body: &[u8]) -> GitHubError {
+        let limited = status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS;
+        let retry_after = header_u64(headers, "retry-after");
+        if limited
+            && (header_u64(headers, "x-ratelimit-remaining") == Some(0) || retry_after.is_some())
+        {
+            // Secondary rate limits only say how long to wait
+            let reset = match retry_after {
+                Some(secs) => Some(SystemTime::now() + Duration::from_secs(secs)),
+                None => header_u64(headers, "x-ratelimit-reset")
+                    .map(|epoch| UNIX_EPOCH + Duration::from_secs(epoch)),
+            };
+            return GitHubError::RateLimited {
+                limit: header_u64(headers, "x-ratelimit-limit"),
+                reset,
+                authenticated: self.token.is_some(),
+            };
+        }
+
+        let message = serde_json::from_slice::<ErrorBody>(body)
+            .map(|body| body.message)
+            .unwrap_or_default();
+        GitHubError::Status { status, message }
+    }
+
+    /// Releases are listed newest first, so there's nothing newer than `min_tag` past a page
+    /// with an older release
+    fn reached_min_tag(&self, page: &ReleasesPage) -> bool {
+        match &self.min_tag {
+            Some(tag) => page
+                .releases
+                .iter()
+                .any(|rel| !rel.draft && rel.tag_name < *tag),
+            None => false,
+        }
+    }
+
+    /// Follows `Link: rel="next"` until the last page, or until releases older than `min_tag`
+    /// show up
     pub fn get_all(&self) -> anyhow::Result<Vec<GitHubRelease>> {
-        let mut releases = Vec::new();
-        let mut page = 1;
+        block_on(self.get_all_with(Io::Blocking))
+    }
 
-        loop {
-            let fetched = self.get_releases(100, page)?;
-            if fetched.is_empty() {
-                break;
-            }
+    #[cfg(feature = "async")]
+    pub async fn get_all_async(&self) -> anyhow::Result<Vec<GitHubRelease>> {
+        self.get_all_with(Io::Async).await
+    }
+
+    async fn get_all_with(&self, io: Io) -> anyhow::Result<Vec<GitHubRelease>> {
+        let mut releases = Vec::new();
+        let mut url = Some(self.releases_url(100, 1));
 
-            releases.extend(fetched);
-            page += 1;
+        while let Some(page_url) = url {
+            let page = self.get_page_with(io, &page_url).await?;
+            url = page.next.clone().filter(|_| !self.reached_min_tag(&page));
+            releases.extend(page.releases);
         }
 
-        Ok(releases
+        releases.retain(|rel| self.filter_release(rel));
+        Ok(releases)
+    }
+}
+
+impl ReleaseSource for GitHubApi {
+    fn id(&self) -> String {
+        SOURCE_ID.to_owned()
+    }
+
+    /// Forks and GitHub Enterprise hosts are cached separately from upstream
+    fn cache_key(&self) -> String {
+        let repo = format!("{}/{}/{}", self.base_url(), self.owner, self.repo);
+        format!("{}-{}", SOURCE_ID, &sha256_hex(repo.as_bytes())[..16])
+    }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases> {
+        Ok(self
+            .get_all()?
             .into_iter()
-            .filter(|rel| self.filter_release(rel))
-            .collect())
+            .map(BepInExRelease::from)
+            .collect::<Vec<_>>()
+            .into())
+    }
+
+    #[cfg(feature = "async")]
+    fn fetch_async(&self) -> BoxFuture<'_, anyhow::Result<SourceReleases>> {
+        Box::pin(async move {
+            Ok(self
+                .get_all_async()
+                .await?
+                .into_iter()
+                .map(BepInExRelease::from)
+                .collect::<Vec<_>>()
+                .into())
+        })
     }
 }
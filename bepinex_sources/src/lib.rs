@@ -1,6 +1,21 @@
+pub mod asset;
 pub mod bepinex;
 pub mod builds;
+pub mod cache;
+pub mod changelog;
+pub mod client;
+pub mod compat;
+pub mod download;
+pub mod extract;
+pub mod fixture;
 pub mod github;
+pub mod hash;
+pub mod local;
 pub mod macros;
+pub mod mirror;
 pub mod models;
+pub mod plan;
+pub mod rules;
+pub mod source;
+pub mod transport;
 pub mod version;
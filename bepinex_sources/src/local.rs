@@ -0,0 +1,166 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use bepinex_helpers::game::get_dll_version_from_bytes;
+use reqwest::Url;
+use zip::ZipArchive;
+
+use crate::{
+    asset::{AssetDescriptor, AssetPlatform, AssetRuntime},
+    bepinex::{BepInExAsset, BepInExRelease, ReleaseFlavor},
+    extract::{core_runtime, pe_arch, CORE_DIR, DOORSTOP_PROXIES},
+    source::{ReleaseSource, SourceReleases},
+};
+
+pub const SOURCE_ID: &str = "local";
+
+const CORE_DLLS: &[&str] = &["BepInEx/core/BepInEx.Core.dll", "BepInEx/core/BepInEx.dll"];
+
+/// Directory of BepInEx zips, e.g. a shared drive for machines without internet access
+#[derive(Debug)]
+pub struct LocalSource {
+    dir: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        LocalSource { dir: dir.into() }
+    }
+
+    pub fn set_dir(&mut self, dir: PathBuf) -> &mut Self {
+        self.dir = dir;
+        self
+    }
+
+    pub fn get_releases(&self) -> anyhow::Result<Vec<BepInExRelease>> {
+        let mut releases: Vec<BepInExRelease> = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_zip = path
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("zip"))
+                .unwrap_or_default();
+            if !is_zip {
+                continue;
+            }
+
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // Renamed zips are listed under the name they'd be published with, so they can
+            // be matched against games like every other asset
+            let (name, descriptor) = match AssetDescriptor::parse(&file_name) {
+                Some(descriptor) => (file_name, descriptor),
+                None => match inspect_archive(&path) {
+                    Some(descriptor) => (descriptor.file_name(), descriptor),
+                    None => continue,
+                },
+            };
+            let version = descriptor.version;
+            let path = fs::canonicalize(&path)?;
+            let size = fs::metadata(&path)?.len();
+            let link = Url::from_file_path(&path)
+                .map_err(|_| anyhow::anyhow!("Invalid path {}", path.display()))?;
+            let asset = BepInExAsset {
+                name,
+                link: link.to_string(),
+                size: Some(size),
+                sha256: None,
+            };
+            let flavor = match version.pre.starts_with("be") {
+                true => ReleaseFlavor::BleedingEdge,
+                false => ReleaseFlavor::Stable,
+            };
+
+            match releases
+                .iter_mut()
+                .find(|r| r.flavor == flavor && r.version == version)
+            {
+                Some(release) => release.assets.push(asset),
+                None => releases.push(BepInExRelease {
+                    version,
+                    assets: vec![asset],
+                    flavor,
+                    source: SOURCE_ID.to_owned(),
+                    date: None,
+                    branch: None,
+                    commits: Vec::new(),
+                    notes: None,
+                    url: None,
+                }),
+            }
+        }
+
+        Ok(releases)
+    }
+}
+
+/// Describes a renamed zip by its contents: the version of the core DLL, the runtime of the
+/// core assemblies and the arch of the doorstop proxy, the same way extracting checks them
+fn inspect_archive(path: &Path) -> Option<AssetDescriptor> {
+    let mut archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    let version = CORE_DLLS.iter().find_map(|name| {
+        let mut file = archive.by_name(name).ok()?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).ok()?;
+        get_dll_version_from_bytes(&bytes).ok()
+    })?;
+
+    let mut descriptor = AssetDescriptor {
+        runtime: None,
+        platform: None,
+        arch: None,
+        build: version
+            .pre
+            .strip_prefix("be.")
+            .and_then(|build| build.parse().ok()),
+        version,
+        hash: None,
+    };
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).ok()?;
+        let path = match file.enclosed_name() {
+            Some(path) => path.to_path_buf(),
+            None => continue,
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if path.starts_with(CORE_DIR) && descriptor.runtime != Some(AssetRuntime::UnityIL2CPP) {
+            descriptor.runtime = core_runtime(&file_name).or(descriptor.runtime);
+        }
+        if path.parent() == Some(Path::new("")) && DOORSTOP_PROXIES.contains(&file_name.as_str()) {
+            if !file_name.ends_with(".dll") {
+                descriptor.platform = Some(AssetPlatform::Unix);
+                continue;
+            }
+            let mut bytes = Vec::new();
+            (&mut file).take(4096).read_to_end(&mut bytes).ok()?;
+            descriptor.platform = Some(AssetPlatform::Windows);
+            descriptor.arch = pe_arch(&bytes);
+        }
+    }
+
+    Some(descriptor)
+}
+
+impl ReleaseSource for LocalSource {
+    fn id(&self) -> String {
+        SOURCE_ID.to_owned()
+    }
+
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases> {
+        Ok(self.get_releases()?.into())
+    }
+}
@@ -4,10 +4,3 @@ macro_rules! s_parse {
         Selector::parse($sel).unwrap()
     };
 }
-
-#[macro_export]
-macro_rules! select {
-    ($elem:expr, $sel:expr) => {
-        $elem.select($sel).next().unwrap()
-    };
-}
@@ -0,0 +1,286 @@
+use std::{
+    fs,
+    path::{Component, Path},
+};
+
+#[cfg(feature = "async")]
+use futures_util::future::BoxFuture;
+use reqwest::Url;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bepinex::{BepInExAsset, BepInExRelease, ReleaseCommit, ReleaseFlavor},
+    client::SourcesClient,
+    hash::sha256_hex,
+    source::{ReleaseSource, SourceReleases},
+    transport::{block_on, Io},
+};
+
+pub const SOURCE_ID: &str = "mirror";
+
+/// Bumped on incompatible changes to the index layout
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// JSON index of a self-hosted mirror. Asset URLs may be relative to the index itself.
+///
+/// ```json
+/// {
+///   "format_version": 1,
+///   "releases": [{
+///     "flavor": "stable",
+///     "version": "5.4.21",
+///     "assets": [{
+///       "name": "BepInEx_x64_5.4.21.0.zip",
+///       "url": "BepInEx_x64_5.4.21.0.zip",
+///       "size": 704286,
+///       "sha256": "…"
+///     }]
+///   }]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorIndex {
+    pub format_version: u32,
+    pub releases: Vec<MirrorRelease>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorRelease {
+    pub flavor: ReleaseFlavor,
+    pub version: Version,
+    pub assets: Vec<MirrorAsset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<ReleaseCommit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorAsset {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl MirrorIndex {
+    pub fn from_releases(releases: &[BepInExRelease]) -> Self {
+        MirrorIndex {
+            format_version: INDEX_FORMAT_VERSION,
+            releases: releases
+                .iter()
+                .map(|release| MirrorRelease {
+                    flavor: release.flavor.clone(),
+                    version: release.version.clone(),
+                    assets: release
+                        .assets
+                        .iter()
+                        .map(|asset| MirrorAsset {
+                            name: asset.name.clone(),
+                            url: asset.link.clone(),
+                            size: asset.size,
+                            sha256: asset.sha256.clone(),
+                        })
+                        .collect(),
+                    date: release.date.clone(),
+                    branch: release.branch.clone(),
+                    commits: release.commits.clone(),
+                    notes: release.notes.clone(),
+                    url: release.url.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Downloads every asset into `dir`, filling in sizes and digests and pointing the URLs
+    /// at the downloaded files (relative to the index, so it should be written into `dir` too).
+    /// Files that are already there with the right digest aren't downloaded again. Asset
+    /// names have to be plain file names, they come from whoever hosts the index.
+    pub fn download_to(&mut self, dir: &Path, client: &SourcesClient) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)?;
+
+        for release in self.releases.iter_mut() {
+            for asset in release.assets.iter_mut() {
+                let mut components = Path::new(&asset.name).components();
+                if !matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                ) {
+                    anyhow::bail!("Asset name {} isn't a plain file name", asset.name);
+                }
+                let path = dir.join(&asset.name);
+                let existing = fs::read(&path).ok().filter(|bytes| match &asset.sha256 {
+                    Some(sha256) => sha256_hex(bytes) == *sha256,
+                    None => false,
+                });
+
+                let bytes = match existing {
+                    Some(bytes) => bytes,
+                    None => {
+                        let bytes = BepInExAsset {
+                            name: asset.name.clone(),
+                            link: asset.url.clone(),
+                            size: asset.size,
+                            sha256: asset.sha256.clone(),
+                        }
+                        .get_bytes(client)?;
+                        fs::write(&path, &bytes)?;
+                        bytes
+                    }
+                };
+
+                asset.size = Some(bytes.len() as u64);
+                asset.sha256 = Some(sha256_hex(&bytes));
+                asset.url = asset.name.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Converts the index into releases, resolving relative asset URLs against `base`
+    pub fn to_releases(&self, base: &Url) -> anyhow::Result<Vec<BepInExRelease>> {
+        if self.format_version > INDEX_FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported mirror index format {}, expected {}",
+                self.format_version,
+                INDEX_FORMAT_VERSION
+            );
+        }
+
+        self.releases
+            .iter()
+            .map(|release| {
+                let assets = release
+                    .assets
+                    .iter()
+                    .map(|asset| {
+                        Ok(BepInExAsset {
+                            name: asset.name.clone(),
+                            link: base.join(&asset.url)?.to_string(),
+                            size: asset.size,
+                            sha256: asset.sha256.clone(),
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                Ok(BepInExRelease {
+                    version: release.version.clone(),
+                    assets,
+                    flavor: release.flavor.clone(),
+                    source: SOURCE_ID.to_owned(),
+                    date: release.date.clone(),
+                    branch: release.branch.clone(),
+                    commits: release.commits.clone(),
+                    notes: release.notes.clone(),
+                    url: release.url.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Releases listed in a [`MirrorIndex`], served over HTTP(S) or from a `file://` URL
+#[derive(Debug)]
+pub struct MirrorSource {
+    index_url: String,
+    client: SourcesClient,
+}
+
+impl MirrorSource {
+    pub fn new(index_url: &str) -> Self {
+        MirrorSource {
+            index_url: index_url.into(),
+            client: SourcesClient::default(),
+        }
+    }
+
+    pub fn set_index_url(&mut self, index_url: String) -> &mut Self {
+        self.index_url = index_url;
+        self
+    }
+
+    pub fn set_client(&mut self, client: SourcesClient) -> &mut Self {
+        self.client = client;
+        self
+    }
+
+    /// The index is fetched like an asset, so `file://` URLs work too
+    fn index_asset(&self) -> BepInExAsset {
+        BepInExAsset {
+            name: "index.json".into(),
+            link: self.index_url.clone(),
+            size: None,
+            sha256: None,
+        }
+    }
+
+    pub fn get_index(&self) -> anyhow::Result<MirrorIndex> {
+        block_on(self.get_index_with(Io::Blocking))
+    }
+
+    pub fn get_releases(&self) -> anyhow::Result<Vec<BepInExRelease>> {
+        block_on(self.get_releases_with(Io::Blocking))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_index_async(&self) -> anyhow::Result<MirrorIndex> {
+        self.get_index_with(Io::Async).await
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn get_releases_async(&self) -> anyhow::Result<Vec<BepInExRelease>> {
+        self.get_releases_with(Io::Async).await
+    }
+
+    async fn get_index_with(&self, io: Io) -> anyhow::Result<MirrorIndex> {
+        let bytes = self.index_asset().get_bytes_with(io, &self.client).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn get_releases_with(&self, io: Io) -> anyhow::Result<Vec<BepInExRelease>> {
+        let base = Url::parse(&self.index_url)?;
+        self.get_index_with(io).await?.to_releases(&base)
+    }
+}
+
+impl ReleaseSource for MirrorSource {
+    fn id(&self) -> String {
+        SOURCE_ID.to_owned()
+    }
+
+    fn cache_key(&self) -> String {
+        format!(
+            "{}-{}",
+            SOURCE_ID,
+            &sha256_hex(self.index_url.as_bytes())[..16]
+        )
+    }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases> {
+        Ok(self.get_releases()?.into())
+    }
+
+    #[cfg(feature = "async")]
+    fn fetch_async(&self) -> BoxFuture<'_, anyhow::Result<SourceReleases>> {
+        Box::pin(async move { Ok(self.get_releases_async().await?.into()) })
+    }
+}
@@ -4,6 +4,16 @@ use semver::Version;
 pub struct BuildsAsset {
     pub name: String,
     pub link: String,
+    /// Approximate, the page only shows sizes like `43.52 MiB`
+    pub size: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct BuildsCommit {
+    /// Short hash, as shown on the page
+    pub hash: String,
+    pub message: String,
+    pub author: Option<String>,
 }
 
 #[derive(Debug)]
@@ -11,4 +21,9 @@ pub struct BuildsRelease {
     pub artifact_id: usize,
     pub version: Version,
     pub assets: Vec<BuildsAsset>,
+    /// Build date as shown on the page, e.g. `2023-03-01T12:34:56Z`
+    pub date: Option<String>,
+    pub branch: Option<String>,
+    /// Commits since the previous build, newest first
+    pub commits: Vec<BuildsCommit>,
 }
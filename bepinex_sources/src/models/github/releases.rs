@@ -5,19 +5,35 @@ use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 
 use crate::version::VersionExt;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubAsset {
     pub name: String,
     pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub download_count: u64,
+    /// `sha256:<hex>`, only set for assets uploaded since mid 2025
+    #[serde(default)]
+    pub digest: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubRelease {
     #[serde(rename = "prerelease")]
     pub pre_release: bool,
     #[serde(deserialize_with = "parse_tag")]
     pub tag_name: Version,
     pub assets: Vec<GitHubAsset>,
+    /// Release notes, in markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub draft: bool,
 }
 
 fn try_to_parse(version: &str) -> Version {
@@ -0,0 +1,213 @@
+use std::{fmt::Display, fs, io, path::PathBuf};
+
+use bepinex_helpers::game::Game;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+use crate::{
+    bepinex::{BepInEx, BepInExAsset, BepInExRelease, ReleaseFlavor},
+    download::{DownloadedArchive, Downloader},
+    extract,
+    version::VersionExt,
+};
+
+/// Something to look at before installing, none of them stop the install
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InstallWarning {
+    /// Anti-cheat, another mod loader or an unknown proxy DLL in the game folder
+    Game {
+        message: String,
+    },
+    /// The release doesn't support the game's runtime, or only partly
+    Runtime {
+        message: String,
+    },
+    Downgrade {
+        installed: Version,
+        target: Version,
+    },
+    KnownBad {
+        reason: String,
+    },
+}
+
+impl Display for InstallWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallWarning::Game { message } | InstallWarning::Runtime { message } => {
+                write!(f, "{}", message)
+            }
+            InstallWarning::Downgrade { installed, .. } => write!(
+                f,
+                "Older than the installed BepInEx {}",
+                installed.display()
+            ),
+            InstallWarning::KnownBad { reason } => write!(f, "Known to be broken: {}", reason),
+        }
+    }
+}
+
+/// Everything installing a release into a game would do, without touching the game folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallPlan {
+    pub game: String,
+    pub game_path: PathBuf,
+    pub version: Version,
+    pub flavor: ReleaseFlavor,
+    pub asset: BepInExAsset,
+    /// Size of the archive in bytes
+    pub download_size: u64,
+    /// The archive was already on disk, in the artifact cache or a local source
+    pub cached: bool,
+    /// Paths are relative to the game folder
+    pub add: Vec<PathBuf>,
+    pub overwrite: Vec<PathBuf>,
+    /// Left over from the installed BepInEx. Installing keeps them, they're only deleted by
+    /// [`prune_stale`](InstallPlan::prune_stale).
+    pub remove: Vec<PathBuf>,
+    /// Existing config files that are kept as they are
+    pub preserve: Vec<PathBuf>,
+    pub warnings: Vec<InstallWarning>,
+}
+
+impl InstallPlan {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deletes the files listed in [`remove`](InstallPlan::remove), meant for after the user
+    /// confirmed the plan and it was installed
+    pub fn prune_stale(&self) -> io::Result<()> {
+        extract::remove_stale(&self.game_path, &self.remove)
+    }
+}
+
+impl Display for InstallPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Install BepInEx {} ({}) into {}",
+            self.version.display(),
+            self.flavor,
+            self.game_path.display()
+        )?;
+        writeln!(
+            f,
+            "Asset: {} ({:.2} MiB{})",
+            self.asset.name,
+            self.download_size as f64 / 1024.0 / 1024.0,
+            if self.cached { ", cached" } else { "" }
+        )?;
+
+        if !self.warnings.is_empty() {
+            writeln!(f, "Warnings:")?;
+            for warning in self.warnings.iter() {
+                writeln!(f, "  {}", warning)?;
+            }
+        }
+        for (title, paths) in [
+            ("Add", &self.add),
+            ("Overwrite", &self.overwrite),
+            ("Remove", &self.remove),
+            ("Preserve", &self.preserve),
+        ] {
+            if paths.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} ({}):", title, paths.len())?;
+            for path in paths.iter() {
+                writeln!(f, "  {}", path.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BepInEx {
+    /// Warnings about installing the release into the game that don't need the archive
+    pub fn install_warnings(&self, game: &Game, release: &BepInExRelease) -> Vec<InstallWarning> {
+        let mut warnings = game
+            .warnings
+            .iter()
+            .map(|warning| InstallWarning::Game {
+                message: warning.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(bad) = self.rules.known_bad(release) {
+            warnings.push(InstallWarning::KnownBad {
+                reason: bad.reason.clone(),
+            });
+        }
+        if !self.rules.supports_runtime(release, game) {
+            let message = match self.rules.min_stable_for(game) {
+                Some(min) => format!("Minimal BepInEx for this game is {}", min),
+                None => "This BepInEx doesn't support the game".to_owned(),
+            };
+            warnings.push(InstallWarning::Runtime { message });
+        }
+        if let Some(message) = self.rules.runtime_warning(release, game) {
+            warnings.push(InstallWarning::Runtime { message });
+        }
+
+        let changelog = self.changelog(game, release);
+        if let (true, Some(installed)) = (changelog.downgrade, changelog.installed) {
+            warnings.push(InstallWarning::Downgrade {
+                installed,
+                target: release.version.clone(),
+            });
+        }
+        warnings
+    }
+
+    /// Dry run of installing the release into the game. The archive is downloaded, into the
+    /// downloader's cache if it has one, to list the files it would write.
+    pub fn plan_install(
+        &self,
+        game: &Game,
+        release: &BepInExRelease,
+        downloader: &Downloader,
+    ) -> anyhow::Result<InstallPlan> {
+        Ok(self.plan_install_with_archive(game, release, downloader)?.0)
+    }
+
+    /// [`plan_install`](BepInEx::plan_install) keeping the archive, so installing the plan
+    /// once it's confirmed doesn't download it again
+    pub fn plan_install_with_archive(
+        &self,
+        game: &Game,
+        release: &BepInExRelease,
+        downloader: &Downloader,
+    ) -> anyhow::Result<(InstallPlan, DownloadedArchive)> {
+        let asset = release.select_asset(game).ok_or_else(|| {
+            anyhow::anyhow!("BepInEx {} has no archive for {}", release, game.name)
+        })?;
+
+        let cached = downloader.is_cached(&asset);
+        let archive = downloader.download(&asset)?;
+        let download_size = fs::metadata(archive.path())?.len();
+        let extraction = extract::plan(&mut ZipArchive::new(archive.open()?)?, game)?;
+
+        let (overwrite, add): (Vec<_>, Vec<_>) = extraction
+            .files
+            .into_iter()
+            .partition(|file| file.overwrites);
+        let plan = InstallPlan {
+            game: game.name.clone(),
+            game_path: game.path.clone(),
+            version: release.version.clone(),
+            flavor: release.flavor.clone(),
+            asset,
+            download_size,
+            cached,
+            add: add.into_iter().map(|file| file.path).collect(),
+            overwrite: overwrite.into_iter().map(|file| file.path).collect(),
+            remove: extraction.stale,
+            preserve: extraction.preserved,
+            warnings: self.install_warnings(game, release),
+        };
+        Ok((plan, archive))
+    }
+}
@@ -0,0 +1,220 @@
+use std::{fs, path::Path};
+
+use bepinex_helpers::game::{Game, GameType};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::AssetRuntime,
+    bepinex::{BepInExRelease, ReleaseFlavor},
+    version::{cmp_precedence, VersionExt},
+};
+
+/// Bumped on incompatible changes to the rules layout
+pub const RULES_FORMAT_VERSION: u32 = 1;
+
+/// Rules shipped with the crate, used when there's no local override
+pub const BUNDLED_RULES: &str = include_str!("../rules.json");
+
+/// Which BepInEx releases can be installed where, kept out of the code so it can be updated
+/// without a new release. See `rules.json` for the bundled defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    pub format_version: u32,
+    /// Oldest stable release offered at all
+    pub min_stable_version: Version,
+    #[serde(default)]
+    pub runtimes: Vec<RuntimeRule>,
+    /// Ordered by `max_metadata_version`
+    #[serde(default)]
+    pub il2cpp_metadata: Vec<MetadataRule>,
+    #[serde(default)]
+    pub known_bad: Vec<KnownBad>,
+    #[serde(default)]
+    pub recommended: Vec<Recommendation>,
+}
+
+/// Oldest release that supports a runtime
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeRule {
+    pub runtime: AssetRuntime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_stable: Option<Version>,
+    /// Oldest bleeding edge artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_build: Option<usize>,
+}
+
+/// First stable release/BE build that can read IL2CPP metadata up to `max_metadata_version`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataRule {
+    pub max_metadata_version: u32,
+    pub min_stable: Version,
+    pub min_build: usize,
+}
+
+/// Selects releases by version and/or bleeding edge artifact id. Build metadata is ignored,
+/// so `6.0.0-be.674` matches `6.0.0-be.674+82077ec`.
+/// A matcher without a version or artifact id matches nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseMatcher {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<ReleaseFlavor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_id: Option<usize>,
+}
+
+impl ReleaseMatcher {
+    pub fn matches(&self, release: &BepInExRelease) -> bool {
+        self.flavor
+            .as_ref()
+            .map(|flavor| *flavor == release.flavor)
+            .unwrap_or(true)
+            && self
+                .version
+                .as_ref()
+                .map(|version| cmp_precedence(version, &release.version).is_eq())
+                .unwrap_or(true)
+            && self
+                .artifact_id
+                .map(|id| release.artifact_id() == Some(id))
+                .unwrap_or(true)
+            && (self.version.is_some() || self.artifact_id.is_some())
+    }
+}
+
+/// Release that is known to be broken and shouldn't be offered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownBad {
+    #[serde(flatten)]
+    pub release: ReleaseMatcher,
+    pub reason: String,
+}
+
+/// Release that is known to work best with a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub app_id: u32,
+    #[serde(flatten)]
+    pub release: ReleaseMatcher,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+impl Rules {
+    pub fn bundled() -> Self {
+        Self::from_json(BUNDLED_RULES).expect("Invalid bundled rules")
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let rules: Rules = serde_json::from_str(json)?;
+        if rules.format_version > RULES_FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported rules format {}, expected {}",
+                rules.format_version,
+                RULES_FORMAT_VERSION
+            );
+        }
+        Ok(rules)
+    }
+
+    /// Reads a local override, falling back to the bundled rules when the file doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match path.exists() {
+            true => Self::from_json(&fs::read_to_string(path)?),
+            false => Ok(Self::bundled()),
+        }
+    }
+
+    pub fn known_bad(&self, release: &BepInExRelease) -> Option<&KnownBad> {
+        self.known_bad
+            .iter()
+            .find(|bad| bad.release.matches(release))
+    }
+
+    pub fn recommended(&self, game: &Game) -> Vec<&Recommendation> {
+        self.recommended
+            .iter()
+            .filter(|r| Some(r.app_id) == game.app_id)
+            .collect()
+    }
+
+    /// Whether the release supports the game's runtime at all
+    pub fn supports_runtime(&self, release: &BepInExRelease, game: &Game) -> bool {
+        let runtime = match &game.ty {
+            Some(ty) => AssetRuntime::from(ty),
+            None => return true,
+        };
+
+        self.runtimes
+            .iter()
+            .filter(|rule| rule.runtime == runtime)
+            .all(|rule| match &release.flavor {
+                ReleaseFlavor::Stable => rule
+                    .min_stable
+                    .as_ref()
+                    .map(|min| release.version >= *min)
+                    .unwrap_or(true),
+                ReleaseFlavor::BleedingEdge => rule
+                    .min_build
+                    .map(|min| {
+                        release
+                            .artifact_id()
+                            .map(|id| id >= min)
+                            .unwrap_or_default()
+                    })
+                    .unwrap_or(true),
+            })
+    }
+
+    /// Oldest stable release that supports the game's runtime, for error messages
+    pub fn min_stable_for(&self, game: &Game) -> Option<&Version> {
+        let runtime = AssetRuntime::from(game.ty.as_ref()?);
+        self.runtimes
+            .iter()
+            .filter(|rule| rule.runtime == runtime)
+            .find_map(|rule| rule.min_stable.as_ref())
+    }
+
+    /// Checks the game's IL2CPP metadata version against what the release is known to support
+    pub fn runtime_warning(&self, release: &BepInExRelease, game: &Game) -> Option<String> {
+        if game.ty != Some(GameType::UnityIL2CPP) {
+            return None;
+        }
+
+        let metadata_version = match game.runtime.metadata_version {
+            Some(version) => version,
+            None => return Some("Couldn't read IL2CPP metadata, it might be encrypted".to_owned()),
+        };
+
+        let required = self
+            .il2cpp_metadata
+            .iter()
+            .find(|rule| metadata_version <= rule.max_metadata_version);
+        let supported = match (required, &release.flavor) {
+            (None, _) => false,
+            (Some(rule), ReleaseFlavor::Stable) => release.version >= rule.min_stable,
+            (Some(rule), ReleaseFlavor::BleedingEdge) => release
+                .artifact_id()
+                .map(|id| id >= rule.min_build)
+                .unwrap_or_default(),
+        };
+
+        match supported {
+            true => None,
+            false => Some(format!(
+                "BepInEx {} might not support IL2CPP metadata v{}",
+                release.version.display(),
+                metadata_version
+            )),
+        }
+    }
+}
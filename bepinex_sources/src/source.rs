@@ -0,0 +1,64 @@
+use std::{error, fmt::Debug, fmt::Display, sync::Arc};
+
+#[cfg(feature = "async")]
+use futures_util::future::BoxFuture;
+
+use crate::bepinex::BepInExRelease;
+
+/// Anything BepInEx releases can be listed from
+pub trait ReleaseSource: Debug + Send + Sync {
+    /// Short name of the source, stored in [`BepInExRelease::source`]
+    fn id(&self) -> String;
+
+    /// File name the releases are cached under, has to differ between sources with the
+    /// same [`id`](ReleaseSource::id)
+    fn cache_key(&self) -> String {
+        self.id()
+    }
+
+    /// Whether releases are worth caching, listing a local folder isn't
+    fn cacheable(&self) -> bool {
+        true
+    }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases>;
+
+    /// Async [`fetch`](ReleaseSource::fetch). The default calls `fetch` on the executor,
+    /// which is only fine for sources that don't wait on the network.
+    #[cfg(feature = "async")]
+    fn fetch_async(&self) -> BoxFuture<'_, anyhow::Result<SourceReleases>> {
+        Box::pin(async move { self.fetch() })
+    }
+}
+
+/// Releases listed by a source. Entries it had to skip don't fail the source, they're
+/// reported in [`BepInEx::errors`](crate::bepinex::BepInEx::errors) next to the releases.
+#[derive(Debug, Default)]
+pub struct SourceReleases {
+    pub releases: Vec<BepInExRelease>,
+    pub skipped: Vec<anyhow::Error>,
+}
+
+impl From<Vec<BepInExRelease>> for SourceReleases {
+    fn from(releases: Vec<BepInExRelease>) -> Self {
+        SourceReleases {
+            releases,
+            skipped: Vec::new(),
+        }
+    }
+}
+
+/// Error of a single source, the other sources' releases are still usable
+#[derive(Debug, Clone)]
+pub struct SourceError {
+    pub source_id: String,
+    pub error: Arc<anyhow::Error>,
+}
+
+impl Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.source_id, self.error)
+    }
+}
+
+impl error::Error for SourceError {}
@@ -0,0 +1,479 @@
+use std::{
+    error,
+    fmt::{Debug, Display},
+    fs,
+    future::Future,
+    io::{self, Read},
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+#[cfg(feature = "async")]
+use futures_util::{
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use reqwest::{
+    blocking::Client,
+    header::{self, HeaderMap},
+    Certificate, Method, Proxy, StatusCode,
+};
+#[cfg(feature = "async")]
+use tokio::time::timeout;
+
+use crate::client::{
+    ClientConfig, DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_TIMEOUT_SECS, DEFAULT_USER_AGENT,
+};
+
+/// Bodies of blocking responses are read in chunks of this size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Every request `bepinex_sources` makes goes through this, so it can be served from
+/// recorded responses instead of the network
+pub trait HttpTransport: Debug + Send + Sync {
+    fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse>;
+
+    #[cfg(feature = "async")]
+    fn send_async(&self, request: HttpRequest) -> BoxFuture<'_, anyhow::Result<AsyncHttpResponse>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+impl HttpRequest {
+    pub fn get(url: &str) -> Self {
+        HttpRequest {
+            method: Method::GET,
+            url: url.to_owned(),
+            headers: HeaderMap::new(),
+        }
+    }
+}
+
+/// Response with a 4xx or 5xx status
+#[derive(Debug, Clone)]
+pub struct HttpStatusError {
+    pub url: String,
+    pub status: StatusCode,
+}
+
+impl Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} returned {}", self.url, self.status)
+    }
+}
+
+impl error::Error for HttpStatusError {}
+
+fn check_status(url: &str, status: StatusCode) -> Result<(), HttpStatusError> {
+    match status.is_client_error() || status.is_server_error() {
+        true => Err(HttpStatusError {
+            url: url.to_owned(),
+            status,
+        }),
+        false => Ok(()),
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Response whose body is read as it arrives
+pub struct HttpResponse {
+    /// After redirects
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    body: Box<dyn Read + Send>,
+}
+
+impl HttpResponse {
+    pub fn new(
+        url: String,
+        status: StatusCode,
+        headers: HeaderMap,
+        body: impl Read + Send + 'static,
+    ) -> Self {
+        HttpResponse {
+            url,
+            status,
+            headers,
+            body: Box::new(body),
+        }
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        content_length(&self.headers)
+    }
+
+    pub fn error_for_status(self) -> Result<Self, HttpStatusError> {
+        check_status(&self.url, self.status)?;
+        Ok(self)
+    }
+
+    pub fn bytes(mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.body.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn text(self) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.bytes()?)?)
+    }
+}
+
+impl Read for HttpResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+impl Debug for HttpResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Async [`HttpResponse`], the body arrives in chunks
+#[cfg(feature = "async")]
+pub struct AsyncHttpResponse {
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    body: BoxStream<'static, anyhow::Result<Vec<u8>>>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncHttpResponse {
+    pub fn new(
+        url: String,
+        status: StatusCode,
+        headers: HeaderMap,
+        body: BoxStream<'static, anyhow::Result<Vec<u8>>>,
+    ) -> Self {
+        AsyncHttpResponse {
+            url,
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Response with the whole body already in memory
+    pub fn from_bytes(url: String, status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Self {
+        Self::new(url, status, headers, stream::iter([Ok(body)]).boxed())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        content_length(&self.headers)
+    }
+
+    pub fn error_for_status(self) -> Result<Self, HttpStatusError> {
+        check_status(&self.url, self.status)?;
+        Ok(self)
+    }
+
+    /// Next part of the body, `None` once it's all read
+    pub async fn chunk(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        self.body.next().await.transpose()
+    }
+
+    pub async fn bytes(mut self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+
+    pub async fn text(self) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.bytes().await?)?)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Debug for AsyncHttpResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncHttpResponse")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How a shared `async` code path does its I/O. The blocking API runs the same code with
+/// [`Io::Blocking`] through [`block_on`], so both behave the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Io {
+    Blocking,
+    #[cfg(feature = "async")]
+    Async,
+}
+
+impl Io {
+    /// Runs `f` on tokio's blocking threads in async mode, right away otherwise
+    pub(crate) async fn run_blocking<T, F>(self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+    {
+        match self {
+            Io::Blocking => f(),
+            #[cfg(feature = "async")]
+            Io::Async => tokio::task::spawn_blocking(f).await?,
+        }
+    }
+}
+
+/// Response of either [`HttpTransport`] method
+#[derive(Debug)]
+pub(crate) enum IoResponse {
+    Blocking(HttpResponse),
+    #[cfg(feature = "async")]
+    Async(AsyncHttpResponse),
+}
+
+impl IoResponse {
+    pub(crate) fn url(&self) -> &str {
+        match self {
+            IoResponse::Blocking(resp) => &resp.url,
+            #[cfg(feature = "async")]
+            IoResponse::Async(resp) => &resp.url,
+        }
+    }
+
+    pub(crate) fn status(&self) -> StatusCode {
+        match self {
+            IoResponse::Blocking(resp) => resp.status,
+            #[cfg(feature = "async")]
+            IoResponse::Async(resp) => resp.status,
+        }
+    }
+
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        match self {
+            IoResponse::Blocking(resp) => &resp.headers,
+            #[cfg(feature = "async")]
+            IoResponse::Async(resp) => &resp.headers,
+        }
+    }
+
+    pub(crate) fn content_length(&self) -> Option<u64> {
+        content_length(self.headers())
+    }
+
+    pub(crate) fn error_for_status(self) -> Result<Self, HttpStatusError> {
+        check_status(self.url(), self.status())?;
+        Ok(self)
+    }
+
+    /// Next part of the body, `None` once it's all read. Blocking responses are read one
+    /// [`CHUNK_SIZE`] at most at a time.
+    pub(crate) async fn chunk(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        match self {
+            IoResponse::Blocking(resp) => {
+                let mut buf = vec![0; CHUNK_SIZE];
+                let read = resp.read(&mut buf)?;
+                buf.truncate(read);
+                Ok(Some(buf).filter(|buf| !buf.is_empty()))
+            }
+            #[cfg(feature = "async")]
+            IoResponse::Async(resp) => resp.chunk().await,
+        }
+    }
+
+    pub(crate) async fn bytes(self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            IoResponse::Blocking(resp) => Ok(resp.bytes()?),
+            #[cfg(feature = "async")]
+            IoResponse::Async(resp) => resp.bytes().await,
+        }
+    }
+
+    pub(crate) async fn text(self) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.bytes().await?)?)
+    }
+}
+
+/// Wakes the thread [`block_on`] parked
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future on the current thread. Meant for [`Io::Blocking`] code, which never has
+/// to wait on an executor.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// [`ClientConfig`] with the proxy and certificates already parsed
+#[derive(Debug)]
+struct ClientOptions {
+    user_agent: String,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    proxy: Option<Proxy>,
+    certificates: Vec<Certificate>,
+}
+
+impl ClientOptions {
+    fn new(config: &ClientConfig) -> anyhow::Result<Self> {
+        let certificates = config
+            .root_certificates
+            .iter()
+            .map(|path| {
+                let bytes = fs::read(path)
+                    .map_err(|e| anyhow::anyhow!("Can't read {}: {}", path.display(), e))?;
+                Ok(match bytes.starts_with(b"-----BEGIN") {
+                    true => Certificate::from_pem(&bytes)?,
+                    false => Certificate::from_der(&bytes)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(ClientOptions {
+            user_agent: config
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| DEFAULT_USER_AGENT.to_owned()),
+            connect_timeout: Duration::from_secs(
+                config
+                    .connect_timeout_secs
+                    .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ),
+            read_timeout: Duration::from_secs(config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+            proxy: config.proxy.as_deref().map(Proxy::all).transpose()?,
+            certificates,
+        })
+    }
+}
+
+/// The blocking and async reqwest builders have the same methods but no common trait
+macro_rules! build_client {
+    ($builder:expr, $options:expr) => {{
+        let options = $options;
+        let mut builder = $builder
+            .user_agent(options.user_agent.as_str())
+            .connect_timeout(options.connect_timeout);
+        if let Some(proxy) = &options.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        for cert in options.certificates.iter() {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        builder.build()
+    }};
+}
+
+/// Sends requests over the network with reqwest.
+///
+/// The blocking client is only created on first use: it runs its own runtime, which panics
+/// when dropped inside an async one.
+#[derive(Debug)]
+pub struct ReqwestTransport {
+    options: ClientOptions,
+    http: Mutex<Option<Client>>,
+    #[cfg(feature = "async")]
+    async_http: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(config: &ClientConfig) -> anyhow::Result<Self> {
+        let options = ClientOptions::new(config)?;
+        Ok(ReqwestTransport {
+            #[cfg(feature = "async")]
+            async_http: build_client!(reqwest::Client::builder(), &options)?,
+            options,
+            http: Mutex::default(),
+        })
+    }
+
+    fn http(&self) -> anyhow::Result<Client> {
+        let mut http = self.http.lock().unwrap();
+        if http.is_none() {
+            // Unlike the async one, the blocking client applies its timeout to each wait for
+            // the server rather than to the whole request
+            let builder = Client::builder().timeout(self.options.read_timeout);
+            *http = Some(build_client!(builder, &self.options)?);
+        }
+        Ok(http.as_ref().unwrap().clone())
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> anyhow::Result<HttpResponse> {
+        let resp = self
+            .http()?
+            .request(request.method, &request.url)
+            .headers(request.headers)
+            .send()?;
+        Ok(HttpResponse::new(
+            resp.url().to_string(),
+            resp.status(),
+            resp.headers().clone(),
+            resp,
+        ))
+    }
+
+    #[cfg(feature = "async")]
+    fn send_async(&self, request: HttpRequest) -> BoxFuture<'_, anyhow::Result<AsyncHttpResponse>> {
+        Box::pin(async move {
+            let read_timeout = self.options.read_timeout;
+            let send = self
+                .async_http
+                .request(request.method, &request.url)
+                .headers(request.headers)
+                .send();
+            let resp = timeout(read_timeout, send)
+                .await
+                .map_err(|_| anyhow::anyhow!("{} didn't respond in time", request.url))??;
+            let (url, status, headers) = (
+                resp.url().to_string(),
+                resp.status(),
+                resp.headers().clone(),
+            );
+
+            // Stops after the first error
+            let body = stream::unfold(Some(resp), move |resp| async move {
+                let mut resp = resp?;
+                match timeout(read_timeout, resp.chunk()).await {
+                    Ok(Ok(Some(chunk))) => Some((Ok(chunk.to_vec()), Some(resp))),
+                    Ok(Ok(None)) => None,
+                    Ok(Err(e)) => Some((Err(e.into()), None)),
+                    Err(_) => Some((Err(anyhow::anyhow!("Timed out reading the response")), None)),
+                }
+            });
+            Ok(AsyncHttpResponse::new(url, status, headers, body.boxed()))
+        })
+    }
+}
@@ -1,4 +1,8 @@
-use semver::{BuildMetadata, Prerelease, Version};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+
+use crate::bepinex::BepInExRelease;
 
 pub trait VersionExt {
     fn mmp(&self) -> String;
@@ -33,3 +37,63 @@ impl VersionExt for Version {
         }
     }
 }
+
+/// Orders versions ignoring build metadata, so `6.0.0-be.674+82077ec` equals `6.0.0-be.674`.
+/// Release lists, rules and changelogs all compare versions with this.
+pub fn cmp_precedence(a: &Version, b: &Version) -> Ordering {
+    (a.major, a.minor, a.patch, &a.pre).cmp(&(b.major, b.minor, b.patch, &b.pre))
+}
+
+/// Which releases to pick, parsed from strings like:
+///
+/// - `latest` or `*`: any stable release
+/// - `^5.4`, `latest ^5.4`: any [`VersionReq`], bleeding edge builds are matched by their
+///   prerelease number, e.g. `>=6.0.0-be.650, <6.0.0-be.700`
+/// - `#674`: a bleeding edge artifact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseRequirement {
+    Version(VersionReq),
+    Artifact(usize),
+}
+
+impl ReleaseRequirement {
+    pub fn matches(&self, release: &BepInExRelease) -> bool {
+        match self {
+            ReleaseRequirement::Version(req) => req.matches(&release.version),
+            ReleaseRequirement::Artifact(id) => release.artifact_id() == Some(*id),
+        }
+    }
+}
+
+impl Default for ReleaseRequirement {
+    fn default() -> Self {
+        ReleaseRequirement::Version(VersionReq::STAR)
+    }
+}
+
+impl FromStr for ReleaseRequirement {
+    type Err = semver::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("latest").map(str::trim).unwrap_or(s);
+
+        if let Some(id) = s.strip_prefix('#').and_then(|id| id.parse().ok()) {
+            return Ok(ReleaseRequirement::Artifact(id));
+        }
+
+        match s.is_empty() {
+            true => Ok(ReleaseRequirement::default()),
+            false => VersionReq::parse(s).map(ReleaseRequirement::Version),
+        }
+    }
+}
+
+impl Display for ReleaseRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReleaseRequirement::Version(req) => write!(f, "{}", req),
+            ReleaseRequirement::Artifact(id) => write!(f, "#{}", id),
+        }
+    }
+}
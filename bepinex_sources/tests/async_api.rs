@@ -0,0 +1,133 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+};
+
+use bepinex_helpers::game::{Game, GameType};
+use bepinex_sources::{
+    bepinex::{BepInEx, BepInExAsset, ChecksumMismatch},
+    builds::BuildsApi,
+    client::SourcesClient,
+    download::{Downloader, Progress},
+    github::GitHubApi,
+    hash::sha256_hex,
+};
+
+use common::{fixture, temp_dir, zip};
+
+/// Serves `routes` by path until the test ends, returning the base URL
+fn serve(routes: Vec<(&'static str, Vec<u8>)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = String::new();
+            {
+                let mut reader = BufReader::new(&mut stream);
+                // Headers end with an empty line
+                while reader.read_line(&mut request).unwrap_or(0) > 2 {}
+            }
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            let path = path.split('?').next().unwrap_or_default();
+
+            let body = routes.iter().find(|(route, _)| *route == path);
+            let (status, body) = match body {
+                Some((_, body)) => ("200 OK", body.as_slice()),
+                None => ("404 Not Found", [].as_slice()),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = stream.write_all(body);
+        }
+    });
+    base
+}
+
+fn archive() -> Vec<u8> {
+    zip(&[
+        ("run_bepinex.sh", b"#!/bin/sh"),
+        ("BepInEx/core/BepInEx.dll", b"core"),
+    ])
+}
+
+#[tokio::test]
+async fn fetches_sources_concurrently() {
+    let releases = fs::read(fixture("github/releases.json")).unwrap();
+    let base = serve(vec![
+        (
+            "/projects/bepinex_be",
+            fs::read(fixture("builds/bepinex_be.html")).unwrap(),
+        ),
+        ("/repos/BepInEx/BepInEx/releases", releases),
+    ]);
+
+    let mut github = GitHubApi::new("BepInEx", "BepInEx");
+    github.set_base_url(Some(base.clone())).set_token(None);
+    let mut bie = BepInEx::default();
+    bie.register(github).register(BuildsApi::new(&base));
+
+    // Spawning checks the future is `Send`
+    let bie = tokio::spawn(async move {
+        bie.fetch_async().await;
+        bie
+    })
+    .await
+    .unwrap();
+    assert!(bie.errors.is_empty(), "{:?}", bie.errors);
+    assert_eq!(bie.releases.len(), 5);
+    assert!(bie.releases.iter().any(|r| r.source == "github"));
+    assert!(bie.releases.iter().any(|r| r.source == "builds"));
+}
+
+#[tokio::test]
+async fn downloads_and_extracts() {
+    let dir = temp_dir("async-download");
+    let bytes = archive();
+    let base = serve(vec![("/BepInEx_unix_5.4.21.0.zip", bytes.clone())]);
+    let asset = BepInExAsset {
+        name: "BepInEx_unix_5.4.21.0.zip".to_owned(),
+        link: format!("{}/BepInEx_unix_5.4.21.0.zip", base),
+        size: None,
+        sha256: Some(sha256_hex(&bytes)),
+    };
+
+    let client = SourcesClient::default();
+    assert_eq!(asset.get_bytes_async(&client).await.unwrap(), bytes);
+
+    let game = Game {
+        name: "Async".to_owned(),
+        path: dir.join("game"),
+        ty: Some(GameType::UnityMono),
+        ..Game::default()
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut downloader = Downloader::new(client);
+    downloader
+        .set_temp_dir(dir.join("tmp"))
+        .set_progress(Some(tx));
+    let plan = asset.download_async(&game, &downloader).await.unwrap();
+    assert_eq!(plan.files.len(), 2);
+    assert!(game.path.join("BepInEx/core/BepInEx.dll").exists());
+    let progress: Progress = rx.try_iter().last().unwrap();
+    assert_eq!(progress.fraction(), Some(1.0));
+
+    let wrong = BepInExAsset {
+        sha256: Some(sha256_hex(b"other")),
+        ..asset
+    };
+    let error = downloader.download_async(&wrong).await.unwrap_err();
+    assert!(error.is::<ChecksumMismatch>());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use bepinex_sources::{
+    bepinex::{BepInEx, BepInExRelease},
+    builds::{parse_size, BuildsApi, BuildsError},
+    fixture::{FixtureResponse, FixtureTransport},
+};
+use semver::Version;
+
+use common::{client, read_fixture};
+
+const BASE_URL: &str = "https://builds.bepinex.dev";
+
+#[test]
+fn parses_builds_page() {
+    let page = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/bepinex_be.html"))
+        .unwrap();
+
+    assert!(page.skipped.is_empty(), "{:?}", page.skipped);
+    let ids: Vec<_> = page.releases.iter().map(|r| r.artifact_id).collect();
+    assert_eq!(ids, [674, 673, 571]);
+
+    let latest = &page.releases[0];
+    assert_eq!(
+        latest.version,
+        Version::parse("6.0.0-be.674+82077ec").unwrap()
+    );
+    assert_eq!(latest.assets.len(), 3);
+    assert_eq!(
+        latest.assets[0].name,
+        "BepInEx-Unity.IL2CPP-win-x64-6.0.0-be.674+82077ec.zip"
+    );
+    assert_eq!(
+        latest.assets[0].link,
+        "https://builds.bepinex.dev/projects/bepinex_be/674/BepInEx-Unity.IL2CPP-win-x64-6.0.0-be.674%2B82077ec.zip"
+    );
+}
+
+#[test]
+fn parses_old_asset_names() {
+    let page = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/bepinex_be.html"))
+        .unwrap();
+
+    let old = page.releases.iter().find(|r| r.artifact_id == 571).unwrap();
+    assert_eq!(old.version, Version::parse("6.0.0-be.571+3a54f7e").unwrap());
+}
+
+#[test]
+fn filters_by_min_build_id() {
+    let page = BuildsApi::new(BASE_URL)
+        .set_min_build_id(Some(673))
+        .parse_page(&read_fixture("builds/bepinex_be.html"))
+        .unwrap();
+
+    let ids: Vec<_> = page.releases.iter().map(|r| r.artifact_id).collect();
+    assert_eq!(ids, [674, 673]);
+}
+
+#[test]
+fn skips_malformed_artifacts() {
+    let page = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/malformed.html"))
+        .unwrap();
+
+    let ids: Vec<_> = page.releases.iter().map(|r| r.artifact_id).collect();
+    assert_eq!(ids, [674]);
+
+    assert_eq!(page.skipped.len(), 5);
+    assert!(matches!(
+        page.skipped[0],
+        BuildsError::MissingElement {
+            artifact_id: Some(673),
+            selector: "a.hash-button"
+        }
+    ));
+    assert!(matches!(&page.skipped[1], BuildsError::InvalidArtifactId(id) if id == "#latest"));
+    assert!(matches!(page.skipped[2], BuildsError::MissingVersion(672)));
+    assert!(matches!(
+        page.skipped[3],
+        BuildsError::InvalidVersion {
+            artifact_id: 671,
+            ..
+        }
+    ));
+    assert!(matches!(
+        page.skipped[4],
+        BuildsError::MissingElement {
+            artifact_id: Some(670),
+            selector: "div.artifacts-list"
+        }
+    ));
+}
+
+#[test]
+fn reports_skipped_artifacts_with_the_releases() {
+    let mut transport = FixtureTransport::default();
+    transport.add(
+        "https://builds.bepinex.dev/projects/bepinex_be",
+        FixtureResponse::bytes(read_fixture("builds/malformed.html")),
+    );
+    let mut builds = BuildsApi::new(BASE_URL);
+    builds.set_client(client(&transport));
+
+    let mut bie = BepInEx::default();
+    bie.register(builds).fetch();
+    assert_eq!(bie.releases.len(), 1);
+    assert_eq!(bie.errors.len(), 5);
+    assert!(bie.errors.iter().all(|e| e.source_id == "builds"));
+    assert_eq!(
+        bie.errors[1].to_string(),
+        "builds: Invalid artifact id `#latest`"
+    );
+
+    bie.revalidate();
+    assert_eq!(bie.errors.len(), 5);
+}
+
+#[test]
+fn page_without_main_is_an_error() {
+    let err = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/no_main.html"))
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        BuildsError::MissingElement {
+            artifact_id: None,
+            selector: "main"
+        }
+    ));
+}
+
+#[test]
+fn parses_build_metadata() {
+    let page = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/bepinex_be.html"))
+        .unwrap();
+
+    let latest = &page.releases[0];
+    assert_eq!(latest.date.as_deref(), Some("2023-03-01T12:34:56Z"));
+    assert_eq!(latest.branch.as_deref(), Some("master"));
+    assert_eq!(latest.commits.len(), 2);
+    assert_eq!(latest.commits[0].hash, "82077ec");
+    assert_eq!(latest.commits[0].message, "Update Il2CppInterop to 1.4.5");
+    assert_eq!(latest.commits[0].author.as_deref(), Some("js6pak"));
+
+    let sizes: Vec<_> = latest.assets.iter().map(|a| a.size).collect();
+    assert_eq!(sizes, [Some(45634027), Some(45602570), Some(1373634)]);
+
+    assert_eq!(page.releases[1].branch, None);
+}
+
+#[test]
+fn labels_bleeding_edge_releases() {
+    let page = BuildsApi::new(BASE_URL)
+        .parse_page(&read_fixture("builds/bepinex_be.html"))
+        .unwrap();
+
+    let release = BepInExRelease::from(page.releases.into_iter().next().unwrap());
+    assert_eq!(
+        release.label(),
+        "#674 – 2023-03-01 – Update Il2CppInterop to 1.4.5"
+    );
+}
+
+#[test]
+fn parses_sizes() {
+    assert_eq!(parse_size("704 KB"), Some(720896));
+    assert_eq!(parse_size("1.5 GiB"), Some(1610612736));
+    assert_eq!(parse_size("12 B"), Some(12));
+    assert_eq!(parse_size("a lot"), None);
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bepinex_sources::{
+    bepinex::{BepInEx, BepInExAsset, ChecksumMismatch},
+    cache::{ArtifactCache, ReleaseCache},
+    hash::sha256_hex,
+    source::{ReleaseSource, SourceReleases},
+};
+
+use common::{builds_releases, temp_dir};
+
+/// Serves the builds fixture, or fails when `online` is off
+#[derive(Debug)]
+struct FixtureSource {
+    online: bool,
+    fetches: Arc<AtomicUsize>,
+}
+
+impl ReleaseSource for FixtureSource {
+    fn id(&self) -> String {
+        "fixture".to_owned()
+    }
+
+    fn fetch(&self) -> anyhow::Result<SourceReleases> {
+        anyhow::ensure!(self.online, "No network");
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+
+        Ok(builds_releases().into())
+    }
+}
+
+fn bepinex(cache: &ReleaseCache, online: bool, fetches: &Arc<AtomicUsize>) -> BepInEx {
+    let mut bie = BepInEx::default();
+    bie.set_cache(Some(cache.clone())).register(FixtureSource {
+        online,
+        fetches: fetches.clone(),
+    });
+    bie.fetch();
+    bie
+}
+
+#[test]
+fn serves_cached_releases_while_fresh() {
+    let cache = ReleaseCache::new(temp_dir("fresh"));
+    let fetches = Arc::new(AtomicUsize::new(0));
+
+    let online = bepinex(&cache, true, &fetches);
+    assert!(online.errors.is_empty());
+    assert!(online.statuses().all(|status| !status.from_cache));
+
+    let offline = bepinex(&cache, false, &fetches);
+    assert!(offline.errors.is_empty());
+    assert_eq!(offline.releases, online.releases);
+    assert!(offline.statuses().all(|s| s.from_cache && !s.stale));
+    assert!(!offline.needs_revalidation());
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+    fs::remove_dir_all(cache.dir()).unwrap();
+}
+
+#[test]
+fn revalidates_stale_releases() {
+    let mut cache = ReleaseCache::new(temp_dir("stale"));
+    cache.set_ttl(Duration::ZERO);
+    let fetches = Arc::new(AtomicUsize::new(0));
+    bepinex(&cache, true, &fetches);
+    std::thread::sleep(Duration::from_millis(1100));
+
+    let mut failing = bepinex(&cache, false, &fetches);
+    assert!(failing.needs_revalidation());
+    failing.revalidate();
+    assert_eq!(failing.errors.len(), 1);
+    assert_eq!(failing.releases.len(), 3);
+
+    let mut bie = bepinex(&cache, true, &fetches);
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    bie.revalidate();
+    assert!(bie.errors.is_empty());
+    assert!(bie.statuses().all(|s| !s.from_cache && !s.stale));
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+    fs::remove_dir_all(cache.dir()).unwrap();
+}
+
+#[test]
+fn offline_without_cache_fails() {
+    let mut cache = ReleaseCache::new(temp_dir("offline"));
+    cache.set_offline(true);
+    let fetches = Arc::new(AtomicUsize::new(0));
+
+    let bie = bepinex(&cache, true, &fetches);
+    assert!(bie.releases.is_empty());
+    assert_eq!(bie.errors.len(), 1);
+    assert_eq!(fetches.load(Ordering::SeqCst), 0);
+}
+
+fn asset(name: &str, sha256: Option<String>) -> BepInExAsset {
+    BepInExAsset {
+        name: name.to_owned(),
+        link: format!("https://builds.bepinex.dev/projects/bepinex_be/{}", name),
+        size: None,
+        sha256,
+    }
+}
+
+#[test]
+fn artifacts_are_found_by_hash_and_link() {
+    let cache = ArtifactCache::new(temp_dir("artifacts"));
+    let bytes = b"BepInEx archive".to_vec();
+
+    cache.put(&asset("a.zip", None), &bytes).unwrap();
+    assert_eq!(cache.get(&asset("a.zip", None)), Some(bytes.clone()));
+    assert_eq!(cache.get(&asset("b.zip", None)), None);
+    // Same archive under another link
+    assert_eq!(
+        cache.get(&asset("b.zip", Some(sha256_hex(&bytes)))),
+        Some(bytes.clone())
+    );
+    assert_eq!(cache.size(), bytes.len() as u64);
+
+    let wrong = asset("c.zip", Some(sha256_hex(b"something else")));
+    let error = cache.put(&wrong, &bytes).unwrap_err();
+    assert!(error.downcast_ref::<ChecksumMismatch>().is_some());
+
+    assert!(cache.clear().unwrap() > 0);
+    assert_eq!(cache.get(&asset("a.zip", None)), None);
+}
+
+#[test]
+fn drops_artifacts_changed_on_disk() {
+    let cache = ArtifactCache::new(temp_dir("artifacts-changed"));
+    let a = asset("a.zip", None);
+    cache.put(&a, b"BepInEx archive").unwrap();
+    let path = cache.get_path(&a).unwrap();
+    assert_ne!(cache.artifacts()[0].modified, 0);
+
+    // Unchanged size and modification time are trusted without hashing the archive again
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    fs::write(&path, b"BepInEx ARCHIVE").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    assert!(cache.get(&a).is_some());
+
+    // Anything else is hashed and dropped if it doesn't match anymore
+    fs::write(&path, b"BepInEx ARCHIVE").unwrap();
+    assert_eq!(cache.get(&a), None);
+    assert!(cache.artifacts().is_empty());
+    assert!(!path.exists());
+
+    cache.clear().unwrap();
+}
+
+#[test]
+fn evicts_least_recently_used_artifacts() {
+    let mut cache = ArtifactCache::new(temp_dir("lru"));
+    cache.set_max_size(20);
+
+    cache.put(&asset("a.zip", None), &[b'a'; 10]).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    cache.put(&asset("b.zip", None), &[b'b'; 10]).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    cache.get(&asset("a.zip", None)).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    cache.put(&asset("c.zip", None), &[b'c'; 10]).unwrap();
+
+    assert!(cache.get(&asset("a.zip", None)).is_some());
+    assert!(cache.get(&asset("b.zip", None)).is_none());
+    assert!(cache.get(&asset("c.zip", None)).is_some());
+    assert_eq!(cache.size(), 20);
+
+    cache.clear().unwrap();
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use bepinex_helpers::game::Game;
+use bepinex_sources::bepinex::{BepInEx, BepInExRelease};
+use semver::Version;
+
+use common::{bepinex, builds_releases, github_releases};
+
+fn game(installed: Option<&str>) -> Game {
+    let mut game = Game::default();
+    game.set_bie(installed.map(|v| Version::parse(v).unwrap()));
+    game
+}
+
+fn release<'a>(bie: &'a BepInEx, version: &str) -> &'a BepInExRelease {
+    bie.releases
+        .iter()
+        .find(|r| r.version.to_string() == version)
+        .unwrap()
+}
+
+fn hashes(bie: &BepInEx, from: usize, to: usize) -> Vec<String> {
+    bie.commits_between(from, to)
+        .into_iter()
+        .map(|c| c.hash[..7].to_owned())
+        .collect()
+}
+
+#[test]
+fn lists_commits_between_builds() {
+    let bie = bepinex(builds_releases());
+
+    assert_eq!(
+        hashes(&bie, 571, 674),
+        vec!["82077ec", "5d5f1a2", "0d06996"]
+    );
+    assert_eq!(hashes(&bie, 673, 674), vec!["82077ec", "5d5f1a2"]);
+    assert_eq!(hashes(&bie, 500, 571), vec!["3a54f7e"]);
+    assert!(hashes(&bie, 674, 674).is_empty());
+    assert!(hashes(&bie, 674, 571).is_empty());
+
+    let commits = bie.commits_between(673, 674);
+    assert_eq!(commits[0].message, "Update Il2CppInterop to 1.4.5");
+    assert_eq!(commits[0].author.as_deref(), Some("js6pak"));
+}
+
+#[test]
+fn changelog_of_a_stable_update() {
+    let bie = bepinex(github_releases());
+    let target = release(&bie, "5.4.21");
+
+    let changelog = bie.changelog(&game(Some("5.4.20")), target);
+    assert_eq!(changelog.installed, Some(Version::new(5, 4, 20)));
+    assert!(!changelog.downgrade);
+    assert_eq!(changelog.entries, vec![target.clone()]);
+    assert!(!changelog.is_empty());
+    assert!(changelog.commits().is_empty());
+}
+
+#[test]
+fn changelog_of_a_bleeding_edge_update() {
+    let bie = bepinex(builds_releases());
+    let target = release(&bie, "6.0.0-be.674+82077ec");
+
+    // Installed builds report their version without the commit hash
+    let changelog = bie.changelog(&game(Some("6.0.0-be.571")), target);
+    assert!(!changelog.downgrade);
+    assert_eq!(
+        changelog
+            .entries
+            .iter()
+            .map(|r| r.artifact_id().unwrap())
+            .collect::<Vec<_>>(),
+        vec![674, 673]
+    );
+    assert_eq!(changelog.commits().len(), 3);
+
+    let reinstall = bie.changelog(&game(Some("6.0.0-be.674")), target);
+    assert!(!reinstall.downgrade);
+    assert!(reinstall.entries.is_empty());
+    assert!(reinstall.is_empty());
+}
+
+#[test]
+fn detects_downgrades() {
+    let bie = bepinex(github_releases());
+    let changelog = bie.changelog(&game(Some("5.4.21")), release(&bie, "5.4.20"));
+
+    assert!(changelog.downgrade);
+    assert!(changelog.entries.is_empty());
+    assert!(changelog.is_empty());
+}
+
+#[test]
+fn changelog_without_installed_bepinex_is_the_target() {
+    let bie = bepinex(github_releases());
+    let target = release(&bie, "5.4.20");
+    let changelog = bie.changelog(&game(None), target);
+
+    assert_eq!(changelog.installed, None);
+    assert!(!changelog.downgrade);
+    assert_eq!(changelog.entries, vec![target.clone()]);
+}
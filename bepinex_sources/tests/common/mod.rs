@@ -0,0 +1,227 @@
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+};
+
+use bepinex_sources::{
+    bepinex::{BepInEx, BepInExRelease},
+    builds::BuildsApi,
+    client::SourcesClient,
+    fixture::FixtureTransport,
+    models::github::releases::GitHubRelease,
+};
+use zip::{write::FileOptions, ZipWriter};
+
+/// Path of a file in `tests/fixtures`
+pub fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+/// Text of a file in `tests/fixtures`
+pub fn read_fixture(path: &str) -> String {
+    fs::read_to_string(fixture(path)).unwrap()
+}
+
+/// Releases of `github/releases.json`, 5.4.21 and 5.4.20
+pub fn github_releases() -> Vec<BepInExRelease> {
+    let json = fs::read(fixture("github/releases.json")).unwrap();
+    serde_json::from_slice::<Vec<GitHubRelease>>(&json)
+        .unwrap()
+        .into_iter()
+        .map(BepInExRelease::from)
+        .collect()
+}
+
+/// Builds of `builds/bepinex_be.html`, 674, 673 and 571
+pub fn builds_releases() -> Vec<BepInExRelease> {
+    let html = read_fixture("builds/bepinex_be.html");
+    BuildsApi::new("https://builds.bepinex.dev")
+        .parse_page(&html)
+        .unwrap()
+        .releases
+        .into_iter()
+        .map(BepInExRelease::from)
+        .collect()
+}
+
+/// [`BepInEx`] with the given releases and the bundled rules, without any sources
+pub fn bepinex(releases: Vec<BepInExRelease>) -> BepInEx {
+    let mut bie = BepInEx::default();
+    bie.releases = releases;
+    bie
+}
+
+/// Empty folder unique to this test run
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("bepinex_sources-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Zip with `files` in it
+pub fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Just enough of a PE header to read the machine type, e.g. `0x8664` for x64
+pub fn proxy(machine: u16) -> Vec<u8> {
+    let mut bytes = vec![0; 0x40];
+    bytes[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+    bytes.extend_from_slice(b"PE\0\0");
+    bytes.extend_from_slice(&machine.to_le_bytes());
+    bytes
+}
+
+fn put(bytes: &mut [u8], at: usize, value: &[u8]) {
+    bytes[at..at + value.len()].copy_from_slice(value);
+}
+
+fn pad4(bytes: &mut Vec<u8>) {
+    bytes.resize((bytes.len() + 3) & !3, 0);
+}
+
+/// One `VS_VERSIONINFO` style block: length, value length, type, key, value and children
+fn version_block(
+    key: &str,
+    ty: u16,
+    value: &[u8],
+    value_len: u16,
+    children: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut block = vec![0; 6];
+    put(&mut block, 2, &value_len.to_le_bytes());
+    put(&mut block, 4, &ty.to_le_bytes());
+    block.extend(key.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+    pad4(&mut block);
+    block.extend_from_slice(value);
+    for child in children {
+        pad4(&mut block);
+        block.extend_from_slice(child);
+    }
+    let len = block.len() as u16;
+    put(&mut block, 0, &len.to_le_bytes());
+    block
+}
+
+/// 32-bit DLL with nothing but a version resource saying `ProductVersion` is `version`
+pub fn core_dll(version: &str) -> Vec<u8> {
+    let mut fixed_info = vec![0; 52];
+    put(&mut fixed_info, 0, &0xFEEF04BDu32.to_le_bytes());
+    put(&mut fixed_info, 4, &0x10000u32.to_le_bytes());
+    let product_version = version
+        .encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    let strings = version_block(
+        "StringFileInfo",
+        1,
+        &[],
+        0,
+        &[version_block(
+            "040904b0",
+            1,
+            &[],
+            0,
+            &[version_block(
+                "ProductVersion",
+                1,
+                &product_version,
+                product_version.len() as u16 / 2,
+                &[],
+            )],
+        )],
+    );
+    let translation = version_block(
+        "VarFileInfo",
+        1,
+        &[],
+        0,
+        &[version_block(
+            "Translation",
+            0,
+            &[0x09, 0x04, 0xB0, 0x04],
+            4,
+            &[],
+        )],
+    );
+    let info = version_block(
+        "VS_VERSION_INFO",
+        0,
+        &fixed_info,
+        52,
+        &[strings, translation],
+    );
+
+    // Resource tree of RT_VERSION, ID 1, en-US pointing at the version info
+    const RVA: u32 = 0x1000;
+    let mut rsrc = vec![0; 0x58];
+    for (at, id, target) in [
+        (0x00, 16, 0x8000_0018u32),
+        (0x18, 1, 0x8000_0030),
+        (0x30, 0x409, 0x48),
+    ] {
+        put(&mut rsrc, at + 14, &1u16.to_le_bytes());
+        put(&mut rsrc, at + 16, &(id as u32).to_le_bytes());
+        put(&mut rsrc, at + 20, &target.to_le_bytes());
+    }
+    put(&mut rsrc, 0x48, &(RVA + 0x58).to_le_bytes());
+    put(&mut rsrc, 0x4C, &(info.len() as u32).to_le_bytes());
+    rsrc.extend(info);
+    let raw_size = (rsrc.len() as u32 + 0x1FF) & !0x1FF;
+    rsrc.resize(raw_size as usize, 0);
+
+    let mut dll = vec![0; 0x200];
+    put(&mut dll, 0, b"MZ");
+    put(&mut dll, 0x3C, &0x40u32.to_le_bytes());
+    put(&mut dll, 0x40, b"PE\0\0");
+    // File header: i386, one section, PE32 optional header, executable 32-bit DLL
+    put(&mut dll, 0x44, &0x14Cu16.to_le_bytes());
+    put(&mut dll, 0x46, &1u16.to_le_bytes());
+    put(&mut dll, 0x54, &0xE0u16.to_le_bytes());
+    put(&mut dll, 0x56, &0x2102u16.to_le_bytes());
+    // Optional header
+    let optional = 0x58;
+    put(&mut dll, optional, &0x10Bu16.to_le_bytes());
+    put(&mut dll, optional + 28, &0x1000_0000u32.to_le_bytes());
+    put(&mut dll, optional + 32, &0x1000u32.to_le_bytes());
+    put(&mut dll, optional + 36, &0x200u32.to_le_bytes());
+    put(&mut dll, optional + 40, &4u16.to_le_bytes());
+    put(&mut dll, optional + 48, &4u16.to_le_bytes());
+    let image_size = RVA + ((raw_size + 0xFFF) & !0xFFF);
+    put(&mut dll, optional + 56, &image_size.to_le_bytes());
+    put(&mut dll, optional + 60, &0x200u32.to_le_bytes());
+    put(&mut dll, optional + 68, &2u16.to_le_bytes());
+    put(&mut dll, optional + 92, &16u32.to_le_bytes());
+    put(&mut dll, optional + 112, &RVA.to_le_bytes());
+    put(&mut dll, optional + 116, &raw_size.to_le_bytes());
+    // .rsrc section header
+    let section = optional + 0xE0;
+    put(&mut dll, section, b".rsrc");
+    put(&mut dll, section + 8, &raw_size.to_le_bytes());
+    put(&mut dll, section + 12, &RVA.to_le_bytes());
+    put(&mut dll, section + 16, &raw_size.to_le_bytes());
+    put(&mut dll, section + 20, &0x200u32.to_le_bytes());
+    put(&mut dll, section + 36, &0x4000_0040u32.to_le_bytes());
+
+    dll.extend(rsrc);
+    dll
+}
+
+/// Client answering from `transport`, requests are recorded in its clones
+pub fn client(transport: &FixtureTransport) -> SourcesClient {
+    let mut client = SourcesClient::default();
+    client.set_transport(transport.clone());
+    client
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use bepinex_helpers::game::{Game, GameArch, GameType};
+use bepinex_sources::{
+    bepinex::{BepInEx, BepInExRelease, ReleaseFlavor},
+    rules::Rules,
+    version::ReleaseRequirement,
+};
+use semver::Version;
+
+use common::{bepinex, builds_releases, github_releases};
+
+fn game(ty: GameType, arch: GameArch) -> Game {
+    let mut game = Game::default();
+    game.set_ty(Some(ty));
+    game.set_arch(arch);
+    game
+}
+
+fn all_releases() -> BepInEx {
+    let mut releases = github_releases();
+    releases.extend(builds_releases());
+    bepinex(releases)
+}
+
+/// Copy of 5.4.21 published under another version
+fn stable(bie: &BepInEx, version: &str) -> BepInExRelease {
+    let mut release = bie.releases[0].clone();
+    release.version = Version::parse(version).unwrap();
+    release
+}
+
+fn versions(releases: Vec<&BepInExRelease>) -> Vec<String> {
+    releases.iter().map(|r| r.version.to_string()).collect()
+}
+
+#[test]
+fn compatible_releases_match_the_game() {
+    let bie = all_releases();
+
+    assert_eq!(
+        versions(bie.compatible_releases(&game(GameType::UnityMono, GameArch::X64))),
+        vec![
+            "6.0.0-be.674+82077ec",
+            "6.0.0-be.571+3a54f7e",
+            "5.4.21",
+            "5.4.20"
+        ]
+    );
+    assert_eq!(
+        versions(bie.compatible_releases(&game(GameType::UnityMono, GameArch::X86))),
+        vec!["5.4.21"]
+    );
+    // 5.x doesn't support IL2CPP at all
+    assert_eq!(
+        versions(bie.compatible_releases(&game(GameType::UnityIL2CPP, GameArch::X64))),
+        vec![
+            "6.0.0-be.674+82077ec",
+            "6.0.0-be.673+0d06996",
+            "6.0.0-be.571+3a54f7e"
+        ]
+    );
+    assert_eq!(
+        versions(bie.compatible_releases(&game(GameType::UnityIL2CPP, GameArch::X86))),
+        vec!["6.0.0-be.674+82077ec"]
+    );
+}
+
+#[test]
+fn skips_releases_older_than_the_minimum() {
+    let mut bie = all_releases();
+    bie.rules.min_stable_version = Version::new(5, 4, 21);
+
+    let game = game(GameType::UnityMono, GameArch::X64);
+    assert!(!bie.is_compatible(&bie.releases[1], &game));
+    assert_eq!(
+        bie.latest_for(&game, ReleaseFlavor::Stable)
+            .map(|r| r.version.to_string()),
+        Some("5.4.21".to_owned())
+    );
+}
+
+#[test]
+fn skips_known_bad_releases() {
+    let mut bie = all_releases();
+    bie.rules = Rules::from_json(
+        r#"{
+            "format_version": 1,
+            "min_stable_version": "5.4.11",
+            "known_bad": [
+                { "version": "5.4.21", "reason": "Breaks the preloader" },
+                { "flavor": "bleeding_edge", "artifact_id": 674, "reason": "Crashes on start" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let game = game(GameType::UnityMono, GameArch::X64);
+    assert_eq!(
+        bie.latest_for(&game, ReleaseFlavor::Stable)
+            .map(|r| r.version.to_string()),
+        Some("5.4.20".to_owned())
+    );
+    assert_eq!(
+        bie.latest_for(&game, ReleaseFlavor::BleedingEdge)
+            .and_then(|r| r.artifact_id()),
+        Some(571)
+    );
+}
+
+#[test]
+fn latest_prefers_stable_versions_over_pre_releases() {
+    let mut bie = all_releases();
+    let pre = stable(&bie, "5.4.22-pre.1");
+    bie.releases.push(pre.clone());
+
+    let game = game(GameType::UnityMono, GameArch::X64);
+    assert_eq!(
+        bie.latest_for(&game, ReleaseFlavor::Stable)
+            .map(|r| r.version.to_string()),
+        Some("5.4.21".to_owned())
+    );
+    assert_eq!(
+        bie.latest_for(&game, ReleaseFlavor::BleedingEdge)
+            .and_then(|r| r.artifact_id()),
+        Some(674)
+    );
+
+    // Pre-releases are still picked when there's nothing else
+    bie.releases.retain(|r| r.version.pre == pre.version.pre);
+    assert_eq!(bie.latest_for(&game, ReleaseFlavor::Stable), Some(pre));
+}
+
+#[test]
+fn latest_for_without_compatible_releases() {
+    let bie = all_releases();
+
+    let il2cpp = game(GameType::UnityIL2CPP, GameArch::X64);
+    assert_eq!(bie.latest_for(&il2cpp, ReleaseFlavor::Stable), None);
+    assert_eq!(
+        bie.recommended_for(&il2cpp, ReleaseFlavor::BleedingEdge)
+            .and_then(|r| r.artifact_id()),
+        Some(674)
+    );
+}
+
+#[test]
+fn requirements_are_limited_to_compatible_releases() {
+    let bie = all_releases();
+    let req = |req: &str| req.parse::<ReleaseRequirement>().unwrap();
+    let x86 = game(GameType::UnityMono, GameArch::X86);
+
+    assert_eq!(
+        versions(bie.matching(&req("~5.4.20"))),
+        vec!["5.4.21", "5.4.20"]
+    );
+    assert_eq!(
+        bie.latest_matching(&req("^5.4"), Some(&x86))
+            .map(|r| r.version.to_string()),
+        Some("5.4.21".to_owned())
+    );
+    assert_eq!(bie.latest_matching(&req("<5.4.21"), Some(&x86)), None);
+    assert_eq!(
+        bie.latest_matching(&req("<5.4.21"), None)
+            .map(|r| r.version.to_string()),
+        Some("5.4.20".to_owned())
+    );
+}
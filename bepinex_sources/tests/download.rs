@@ -0,0 +1,201 @@
+mod common;
+
+use std::{fs, path::Path, sync::mpsc};
+
+use bepinex_sources::{
+    bepinex::{BepInExAsset, ChecksumMismatch},
+    cache::ArtifactCache,
+    client::SourcesClient,
+    download::{CancelToken, Cancelled, Downloader, Progress},
+    fixture::{FixtureResponse, FixtureTransport},
+    hash::sha256_hex,
+};
+use reqwest::{
+    header::{ETAG, IF_RANGE, RANGE},
+    Url,
+};
+
+use common::{client, temp_dir};
+
+const ARCHIVE: &[u8] = b"BepInEx archive";
+
+fn asset(link: String, sha256: Option<String>) -> BepInExAsset {
+    BepInExAsset {
+        name: "BepInEx_x64_5.4.21.0.zip".to_owned(),
+        link,
+        size: Some(ARCHIVE.len() as u64),
+        sha256,
+    }
+}
+
+#[test]
+fn verifies_local_archives() {
+    let dir = temp_dir("download-local");
+    let path = dir.join("BepInEx_x64_5.4.21.0.zip");
+    fs::write(&path, ARCHIVE).unwrap();
+    let link = Url::from_file_path(&path).unwrap().to_string();
+    let downloader = Downloader::new(SourcesClient::default());
+
+    let archive = downloader
+        .download(&asset(link.clone(), Some(sha256_hex(ARCHIVE))))
+        .unwrap();
+    assert_eq!(archive.path(), path);
+    drop(archive);
+    assert!(path.exists());
+
+    let error = downloader
+        .download(&asset(link, Some(sha256_hex(b"other"))))
+        .unwrap_err();
+    assert!(error.is::<ChecksumMismatch>());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn serves_cached_archives_with_progress() {
+    let dir = temp_dir("download-cached");
+    let cache = ArtifactCache::new(dir.join("artifacts"));
+    // Never reached, the archive is cached
+    let asset = asset("http://127.0.0.1:9/BepInEx.zip".to_owned(), None);
+    cache.put(&asset, ARCHIVE).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let mut downloader = Downloader::new(SourcesClient::default());
+    downloader
+        .set_cache(Some(cache))
+        .set_temp_dir(dir.join("tmp"))
+        .set_progress(Some(tx));
+
+    let archive = downloader.download(&asset).unwrap();
+    assert_eq!(fs::read(archive.path()).unwrap(), ARCHIVE);
+    let progress: Progress = rx.try_iter().last().unwrap();
+    assert_eq!(progress.fraction(), Some(1.0));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cancelled_downloads_stop_before_connecting() {
+    let dir = temp_dir("download-cancel");
+    let cancel = CancelToken::default();
+    cancel.cancel();
+
+    let mut downloader = Downloader::new(SourcesClient::default());
+    downloader
+        .set_temp_dir(dir.join("tmp"))
+        .set_cancel_token(cancel);
+    let error = downloader
+        .download(&asset("http://127.0.0.1:9/BepInEx.zip".to_owned(), None))
+        .unwrap_err();
+    assert!(error.is::<Cancelled>());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Downloader for `link`, with `partial` left behind by an interrupted download that got
+/// `validator` as its `ETag`
+fn interrupted(
+    dir: &Path,
+    link: &str,
+    response: FixtureResponse,
+    partial: &[u8],
+    validator: Option<&str>,
+) -> (Downloader, FixtureTransport) {
+    let mut transport = FixtureTransport::default();
+    transport.add(link, response);
+    let mut downloader = Downloader::new(client(&transport));
+    downloader.set_temp_dir(dir.join("tmp"));
+
+    let part = downloader.part_path(&asset(link.to_owned(), Some(sha256_hex(ARCHIVE))));
+    fs::create_dir_all(part.parent().unwrap()).unwrap();
+    fs::write(&part, partial).unwrap();
+    if let Some(validator) = validator {
+        fs::write(part.with_extension("validator"), validator).unwrap();
+    }
+    (downloader, transport)
+}
+
+fn with_etag(etag: &str) -> FixtureResponse {
+    let mut response = FixtureResponse::bytes(ARCHIVE);
+    response.set_header(ETAG, etag);
+    response
+}
+
+#[test]
+fn resumes_partial_downloads() {
+    let dir = temp_dir("download-resume");
+    let link = "https://example.com/BepInEx_x64_5.4.21.0.zip";
+    let (downloader, transport) = interrupted(
+        &dir,
+        link,
+        with_etag("\"v1\""),
+        &ARCHIVE[..7],
+        Some("\"v1\""),
+    );
+
+    let asset = asset(link.to_owned(), Some(sha256_hex(ARCHIVE)));
+    let archive = downloader.download(&asset).unwrap();
+    assert_eq!(fs::read(archive.path()).unwrap(), ARCHIVE);
+    let request = &transport.requests()[0];
+    assert_eq!(request.headers[RANGE], "bytes=7-");
+    assert_eq!(request.headers[IF_RANGE], "\"v1\"");
+    assert!(!downloader
+        .part_path(&asset)
+        .with_extension("validator")
+        .exists());
+    drop(archive);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn restarts_when_the_asset_changed() {
+    let dir = temp_dir("download-changed");
+    let link = "https://example.com/BepInEx_x64_5.4.21.0.zip";
+    let (downloader, transport) =
+        interrupted(&dir, link, with_etag("\"v2\""), b"Old rel", Some("\"v1\""));
+
+    let archive = downloader
+        .download(&asset(link.to_owned(), Some(sha256_hex(ARCHIVE))))
+        .unwrap();
+    assert_eq!(fs::read(archive.path()).unwrap(), ARCHIVE);
+    assert_eq!(transport.requests().len(), 1);
+    drop(archive);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn restarts_partial_downloads_without_a_validator() {
+    let dir = temp_dir("download-no-validator");
+    let link = "https://example.com/BepInEx_x64_5.4.21.0.zip";
+    let (downloader, transport) = interrupted(
+        &dir,
+        link,
+        FixtureResponse::bytes(ARCHIVE),
+        b"Old rel",
+        None,
+    );
+
+    let archive = downloader
+        .download(&asset(link.to_owned(), Some(sha256_hex(ARCHIVE))))
+        .unwrap();
+    assert_eq!(fs::read(archive.path()).unwrap(), ARCHIVE);
+    assert!(!transport.requests()[0].headers.contains_key(RANGE));
+    drop(archive);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn partial_downloads_are_kept_per_asset() {
+    let downloader = Downloader::new(SourcesClient::default());
+    let link = "https://example.com/BepInEx_x64_5.4.21.0.zip";
+    let old = asset(link.to_owned(), Some(sha256_hex(b"old")));
+    let new = asset(link.to_owned(), Some(sha256_hex(ARCHIVE)));
+    assert_ne!(downloader.part_path(&old), downloader.part_path(&new));
+    assert_eq!(
+        downloader.part_path(&new),
+        downloader.part_path(&new.clone())
+    );
+}
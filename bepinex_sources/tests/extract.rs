@@ -0,0 +1,117 @@
+mod common;
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use bepinex_helpers::game::{Game, GameType};
+use bepinex_sources::{
+    asset::{AssetArch, AssetRuntime},
+    extract::{self, ExtractError},
+};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use common::{proxy, temp_dir, zip};
+
+fn archive(files: &[(&str, &[u8])]) -> ZipArchive<Cursor<Vec<u8>>> {
+    ZipArchive::new(Cursor::new(zip(files))).unwrap()
+}
+
+fn mono_x64() -> ZipArchive<Cursor<Vec<u8>>> {
+    archive(&[
+        ("winhttp.dll", &proxy(0x8664)),
+        ("doorstop_config.ini", b"[UnityDoorstop]"),
+        ("BepInEx/core/BepInEx.dll", b"core"),
+    ])
+}
+
+fn game(name: &str, ty: GameType) -> Game {
+    Game {
+        name: name.to_owned(),
+        arch: "x64".to_owned(),
+        path: temp_dir(name),
+        ty: Some(ty),
+        ..Game::default()
+    }
+}
+
+#[test]
+fn plans_and_extracts_bepinex() {
+    let game = game("extract", GameType::UnityMono);
+    fs::write(game.path.join("doorstop_config.ini"), "old").unwrap();
+
+    let plan = extract::plan(&mut mono_x64(), &game).unwrap();
+    assert_eq!(plan.runtime, Some(AssetRuntime::UnityMono));
+    assert_eq!(plan.arch, Some(AssetArch::X64));
+    assert_eq!(plan.files.len(), 3);
+    let overwritten = plan.overwritten().map(|f| &f.path).collect::<Vec<_>>();
+    assert_eq!(overwritten, [&PathBuf::from("doorstop_config.ini")]);
+    assert!(!game.path.join("winhttp.dll").exists());
+
+    assert_eq!(extract::extract(&mut mono_x64(), &game).unwrap(), plan);
+    let core = game.path.join(Path::new("BepInEx/core/BepInEx.dll"));
+    assert_eq!(fs::read(core).unwrap(), b"core");
+
+    fs::remove_dir_all(&game.path).unwrap();
+}
+
+#[test]
+fn rejects_paths_outside_the_game() {
+    let game = game("extract-unsafe", GameType::UnityMono);
+    for name in [
+        "../winhttp.dll",
+        "/etc/winhttp.dll",
+        "BepInEx/../../core.dll",
+    ] {
+        let mut zip = archive(&[("BepInEx/core/BepInEx.dll", b"core"), (name, b"evil")]);
+        let error = extract::extract(&mut zip, &game).unwrap_err();
+        assert!(matches!(error, ExtractError::UnsafePath(_)), "{name}");
+    }
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.add_symlink("BepInEx/plugins", "../../..", FileOptions::default())
+        .unwrap();
+    let mut zip = ZipArchive::new(zip.finish().unwrap()).unwrap();
+    let error = extract::plan(&mut zip, &game).unwrap_err();
+    assert!(matches!(error, ExtractError::UnsafeSymlink { .. }));
+
+    assert_eq!(fs::read_dir(&game.path).unwrap().count(), 0);
+    fs::remove_dir_all(&game.path).unwrap();
+}
+
+#[test]
+fn rejects_incomplete_or_mismatched_archives() {
+    let mono = game("extract-mismatch", GameType::UnityMono);
+    let mut no_core = archive(&[("winhttp.dll", &proxy(0x8664))]);
+    assert!(matches!(
+        extract::plan(&mut no_core, &mono),
+        Err(ExtractError::MissingCore)
+    ));
+    let mut no_proxy = archive(&[("BepInEx/core/BepInEx.dll", b"core")]);
+    assert!(matches!(
+        extract::plan(&mut no_proxy, &mono),
+        Err(ExtractError::MissingDoorstop)
+    ));
+
+    let mut x86 = archive(&[
+        ("winhttp.dll", &proxy(0x014C)),
+        ("BepInEx/core/BepInEx.dll", b"core"),
+    ]);
+    assert!(matches!(
+        extract::plan(&mut x86, &mono),
+        Err(ExtractError::ArchMismatch { .. })
+    ));
+
+    let il2cpp = Game {
+        ty: Some(GameType::UnityIL2CPP),
+        ..mono.clone()
+    };
+    assert!(matches!(
+        extract::plan(&mut mono_x64(), &il2cpp),
+        Err(ExtractError::RuntimeMismatch { .. })
+    ));
+
+    fs::remove_dir_all(&mono.path).unwrap();
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bepinex_sources::{
+    bepinex::BepInEx,
+    fixture::{FixtureResponse, FixtureTransport},
+    github::{next_link, GitHubApi, GitHubError},
+    source::ReleaseSource,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, LINK},
+    StatusCode,
+};
+
+use common::client;
+
+const GITHUB_RELEASES: &str = "https://api.github.com/repos/BepInEx/BepInEx/releases";
+
+fn headers(link: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(LINK, HeaderValue::from_static(link));
+    headers
+}
+
+#[test]
+fn finds_next_link() {
+    let headers = headers(
+        r#"<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=3>; rel="last""#,
+    );
+    assert_eq!(
+        next_link(&headers).as_deref(),
+        Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+    );
+}
+
+#[test]
+fn last_page_has_no_next_link() {
+    let headers = headers(
+        r#"<https://api.github.com/repositories/1/releases?per_page=100&page=1>; rel="prev", <https://api.github.com/repositories/1/releases?per_page=100&page=1>; rel="first""#,
+    );
+    assert_eq!(next_link(&headers), None);
+    assert_eq!(next_link(&HeaderMap::new()), None);
+}
+
+/// Error of fetching the releases when GitHub answers with `response`
+fn error_of(response: FixtureResponse, token: Option<&str>) -> GitHubError {
+    let mut transport = FixtureTransport::default();
+    transport.add(GITHUB_RELEASES, response);
+
+    let mut github = GitHubApi::new("BepInEx", "BepInEx");
+    github
+        .set_client(client(&transport))
+        .set_token(token.map(str::to_owned));
+    github
+        .get_releases(30, 1)
+        .unwrap_err()
+        .downcast::<GitHubError>()
+        .unwrap()
+}
+
+fn forbidden(headers: &[(&'static str, &str)]) -> FixtureResponse {
+    let mut response = FixtureResponse::bytes(r#"{ "message": "API rate limit exceeded" }"#);
+    response.set_status(StatusCode::FORBIDDEN);
+    for (name, value) in headers {
+        response.set_header(HeaderName::from_static(name), value);
+    }
+    response
+}
+
+#[test]
+fn parses_rate_limit_headers() {
+    let response = forbidden(&[
+        ("x-ratelimit-limit", "60"),
+        ("x-ratelimit-remaining", "0"),
+        ("x-ratelimit-reset", "1700000000"),
+    ]);
+
+    match error_of(response, None) {
+        GitHubError::RateLimited {
+            limit,
+            reset,
+            authenticated,
+        } => {
+            assert_eq!(limit, Some(60));
+            assert_eq!(reset, Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+            assert!(!authenticated);
+        }
+        error => panic!("expected a rate limit error, got {:?}", error),
+    }
+}
+
+#[test]
+fn secondary_rate_limits_wait_for_retry_after() {
+    let response = forbidden(&[("retry-after", "120")]);
+
+    match error_of(response, Some("token")) {
+        GitHubError::RateLimited {
+            limit,
+            reset,
+            authenticated,
+        } => {
+            assert_eq!(limit, None);
+            let wait = reset.unwrap().duration_since(SystemTime::now()).unwrap();
+            assert!(wait <= Duration::from_secs(120) && wait > Duration::from_secs(60));
+            assert!(authenticated);
+        }
+        error => panic!("expected a rate limit error, got {:?}", error),
+    }
+}
+
+#[test]
+fn forbidden_without_rate_limit_headers_is_a_status_error() {
+    let error = error_of(forbidden(&[("x-ratelimit-remaining", "59")]), None);
+    match &error {
+        GitHubError::Status { status, message } => {
+            assert_eq!(*status, StatusCode::FORBIDDEN);
+            assert_eq!(message, "API rate limit exceeded");
+        }
+        error => panic!("expected a status error, got {:?}", error),
+    }
+    assert_eq!(
+        error.to_string(),
+        "GitHub API returned 403 Forbidden: API rate limit exceeded"
+    );
+
+    let mut response = FixtureResponse::bytes("Not Found");
+    response.set_status(StatusCode::NOT_FOUND);
+    assert_eq!(
+        error_of(response, None).to_string(),
+        "GitHub API returned 404 Not Found"
+    );
+}
+
+#[test]
+fn forks_and_hosts_have_their_own_cache_key() {
+    let upstream = GitHubApi::new("BepInEx", "BepInEx");
+    let fork = GitHubApi::new("someone", "BepInEx");
+    let mut enterprise = GitHubApi::new("BepInEx", "BepInEx");
+    enterprise.set_base_url(Some("https://github.example.com/api/v3".to_owned()));
+
+    let keys = [&upstream, &fork, &enterprise].map(|github| github.cache_key());
+    assert!(keys.iter().all(|key| key.starts_with("github-")));
+    assert_ne!(keys[0], keys[1]);
+    assert_ne!(keys[0], keys[2]);
+    assert_ne!(keys[1], keys[2]);
+    assert_eq!(
+        GitHubApi::new("BepInEx", "BepInEx").cache_key(),
+        upstream.cache_key()
+    );
+}
+
+#[test]
+fn debug_output_leaves_out_the_token() {
+    let mut github = GitHubApi::new("BepInEx", "BepInEx");
+    github.set_token(Some("ghp_secret".to_owned()));
+    assert!(format!("{:?}", github).contains("<redacted>"));
+
+    let mut bie = BepInEx::default();
+    bie.register(github);
+    assert!(!format!("{:?}", bie).contains("ghp_secret"));
+}
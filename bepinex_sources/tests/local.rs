@@ -0,0 +1,97 @@
+mod common;
+
+use std::fs;
+
+use bepinex_helpers::game::{Game, GameType};
+use bepinex_sources::{
+    bepinex::{AssetDownloader, ReleaseFlavor},
+    client::SourcesClient,
+    download::Downloader,
+    local::LocalSource,
+};
+use reqwest::Url;
+use semver::Version;
+
+use common::{core_dll, proxy, temp_dir, zip};
+
+#[test]
+fn groups_local_archives_into_releases() {
+    let dir = temp_dir("local");
+    let archive = zip(&[("BepInEx/core/BepInEx.dll", b"core")]);
+    for name in [
+        "BepInEx_x64_5.4.21.0.zip",
+        "BepInEx_x86_5.4.21.0.zip",
+        "BepInEx_UnityIL2CPP_x64_6.0.0-be.674+82077ec.zip",
+        // Neither the name nor the core DLL has a version
+        "renamed.zip",
+    ] {
+        fs::write(dir.join(name), &archive).unwrap();
+    }
+    fs::write(dir.join("BepInEx_x64_5.4.20.0.txt"), "not a zip").unwrap();
+    fs::write(dir.join("broken.zip"), "not a zip either").unwrap();
+
+    let mut releases = LocalSource::new(&dir).get_releases().unwrap();
+    releases.sort_by(|a, b| a.version.cmp(&b.version));
+    assert_eq!(releases.len(), 2);
+
+    let stable = &releases[0];
+    assert_eq!(stable.version, Version::new(5, 4, 21));
+    assert_eq!(stable.flavor, ReleaseFlavor::Stable);
+    assert_eq!(stable.source, "local");
+    let mut names: Vec<_> = stable.assets.iter().map(|a| a.name.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        ["BepInEx_x64_5.4.21.0.zip", "BepInEx_x86_5.4.21.0.zip"]
+    );
+    let asset = &stable.assets[0];
+    assert_eq!(asset.size, Some(archive.len() as u64));
+    let path = Url::parse(&asset.link).unwrap().to_file_path().unwrap();
+    assert_eq!(fs::read(path).unwrap(), archive);
+
+    let be = &releases[1];
+    assert_eq!(be.version, Version::parse("6.0.0-be.674+82077ec").unwrap());
+    assert_eq!(be.flavor, ReleaseFlavor::BleedingEdge);
+
+    assert!(LocalSource::new(dir.join("missing"))
+        .get_releases()
+        .is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn installs_renamed_archives() {
+    let dir = temp_dir("local-renamed");
+    let archive = zip(&[
+        ("winhttp.dll", &proxy(0x8664)),
+        ("BepInEx/core/BepInEx.dll", &core_dll("5.4.21.0")),
+    ]);
+    fs::write(dir.join("bepinex for my server.zip"), archive).unwrap();
+
+    let releases = LocalSource::new(&dir).get_releases().unwrap();
+    assert_eq!(releases.len(), 1);
+    assert_eq!(releases[0].version, Version::new(5, 4, 21));
+    // Listed under the name it would have been published with
+    assert_eq!(
+        releases[0].assets[0].name,
+        "BepInEx-Unity.Mono-win-x64-5.4.21.zip"
+    );
+
+    let game = Game {
+        arch: "x64".to_owned(),
+        path: dir.join("game"),
+        ty: Some(GameType::UnityMono),
+        ..Game::default()
+    };
+    let x86 = Game {
+        arch: "x86".to_owned(),
+        ..game.clone()
+    };
+    assert!(releases[0].select_asset(&x86).is_none());
+    let asset = releases[0].select_asset(&game).unwrap();
+    asset
+        .download(&game, &Downloader::new(SourcesClient::default()))
+        .unwrap();
+    assert!(game.path.join("BepInEx/core/BepInEx.dll").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use std::fs;
+
+use bepinex_sources::{
+    bepinex::ReleaseFlavor,
+    client::SourcesClient,
+    hash::sha256_hex,
+    mirror::{MirrorIndex, MirrorSource, INDEX_FORMAT_VERSION},
+};
+use reqwest::Url;
+use semver::Version;
+
+use common::temp_dir;
+
+#[test]
+fn reads_and_exports_mirror_indexes() {
+    let dir = temp_dir("mirror");
+    let upstream = dir.join("upstream");
+    fs::create_dir_all(&upstream).unwrap();
+    fs::write(upstream.join("BepInEx_x64_5.4.21.0.zip"), "archive").unwrap();
+    let index = r#"{
+            "format_version": 1,
+            "releases": [{
+                "flavor": "stable",
+                "version": "5.4.21",
+                "branch": "v5-lts",
+                "assets": [
+                    { "name": "BepInEx_x64_5.4.21.0.zip", "url": "BepInEx_x64_5.4.21.0.zip" },
+                    { "name": "BepInEx_x86_5.4.21.0.zip", "url": "https://example.com/x86.zip" }
+                ]
+            }]
+        }"#;
+    fs::write(upstream.join("index.json"), index).unwrap();
+    let index_url = Url::from_file_path(upstream.join("index.json")).unwrap();
+
+    let source = MirrorSource::new(index_url.as_str());
+    let releases = source.get_releases().unwrap();
+    assert_eq!(releases.len(), 1);
+    let release = &releases[0];
+    assert_eq!(release.version, Version::new(5, 4, 21));
+    assert_eq!(release.flavor, ReleaseFlavor::Stable);
+    assert_eq!(release.branch.as_deref(), Some("v5-lts"));
+    // Relative URLs point next to the index, absolute ones are kept
+    let local = Url::from_file_path(upstream.join("BepInEx_x64_5.4.21.0.zip")).unwrap();
+    assert_eq!(release.assets[0].link, local.as_str());
+    assert_eq!(release.assets[1].link, "https://example.com/x86.zip");
+
+    let mut exported = MirrorIndex::from_releases(&releases[..1]);
+    exported.releases[0].assets.truncate(1);
+    let copy = dir.join("copy");
+    exported
+        .download_to(&copy, &SourcesClient::default())
+        .unwrap();
+    let asset = &exported.releases[0].assets[0];
+    assert_eq!(asset.url, "BepInEx_x64_5.4.21.0.zip");
+    assert_eq!(asset.size, Some(7));
+    assert_eq!(asset.sha256, Some(sha256_hex(b"archive")));
+    assert_eq!(fs::read(copy.join(&asset.name)).unwrap(), b"archive");
+
+    exported.write(&copy.join("index.json")).unwrap();
+    let copy_url = Url::from_file_path(copy.join("index.json")).unwrap();
+    assert_eq!(
+        MirrorSource::new(copy_url.as_str()).get_index().unwrap(),
+        exported
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rejects_invalid_indexes() {
+    let dir = temp_dir("mirror-invalid");
+    let path = dir.join("index.json");
+    let url = Url::from_file_path(&path).unwrap();
+    let source = MirrorSource::new(url.as_str());
+
+    // One release with a version that isn't semver
+    fs::write(
+        &path,
+        r#"{
+            "format_version": 1,
+            "releases": [
+                { "flavor": "stable", "version": "5.4.21", "assets": [] },
+                { "flavor": "stable", "version": "5.4.x", "assets": [] }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert!(source.get_releases().is_err());
+
+    let newer = format!(
+        r#"{{ "format_version": {}, "releases": [] }}"#,
+        INDEX_FORMAT_VERSION + 1
+    );
+    fs::write(&path, newer).unwrap();
+    assert!(source.get_index().is_ok());
+    let error = source.get_releases().unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Unsupported mirror index format"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn keeps_downloads_inside_the_mirror_dir() {
+    let dir = temp_dir("mirror-hostile");
+    let upstream = dir.join("upstream");
+    fs::create_dir_all(&upstream).unwrap();
+    fs::write(upstream.join("archive.zip"), "archive").unwrap();
+    let mirror = dir.join("mirror");
+
+    for name in [
+        "../escaped.zip",
+        "/tmp/escaped.zip",
+        "nested/escaped.zip",
+        ".",
+        "",
+    ] {
+        let index = format!(
+            r#"{{
+                "format_version": 1,
+                "releases": [{{
+                    "flavor": "stable",
+                    "version": "5.4.21",
+                    "assets": [{{ "name": {:?}, "url": "archive.zip" }}]
+                }}]
+            }}"#,
+            name
+        );
+        fs::write(upstream.join("index.json"), index).unwrap();
+        let index_url = Url::from_file_path(upstream.join("index.json")).unwrap();
+        let releases = MirrorSource::new(index_url.as_str())
+            .get_releases()
+            .unwrap();
+
+        let error = MirrorIndex::from_releases(&releases)
+            .download_to(&mirror, &SourcesClient::default())
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("isn't a plain file name"),
+            "{name}"
+        );
+    }
+    assert!(!dir.join("escaped.zip").exists());
+    assert!(!mirror.join("nested").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use bepinex_helpers::game::{Game, GameType};
+use bepinex_sources::{
+    bepinex::{AssetDownloader, BepInEx},
+    builds::BuildsApi,
+    download::Downloader,
+    fixture::{FixtureResponse, FixtureTransport},
+    github::GitHubApi,
+    transport::HttpStatusError,
+};
+use reqwest::{header::ACCEPT, StatusCode};
+use semver::Version;
+
+use common::{client, fixture, proxy, temp_dir, zip};
+
+const GITHUB_RELEASES: &str = "https://api.github.com/repos/BepInEx/BepInEx/releases";
+const GITHUB_DOWNLOADS: &str = "https://github.com/BepInEx/BepInEx/releases/download";
+
+fn write_archive(path: &Path) {
+    let archive = zip(&[
+        ("winhttp.dll", &proxy(0x8664)),
+        ("BepInEx/core/BepInEx.dll", b"core"),
+    ]);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, archive).unwrap();
+}
+
+#[test]
+fn fetches_and_installs_offline() {
+    let dir = temp_dir("pipeline");
+    write_archive(&dir.join("downloads/v5.4.21/BepInEx_x64_5.4.21.0.zip"));
+
+    let mut transport = FixtureTransport::default();
+    transport
+        .add(
+            GITHUB_RELEASES,
+            FixtureResponse::file(fixture("github/releases.json")),
+        )
+        .add(
+            "https://builds.bepinex.dev/projects/bepinex_be",
+            FixtureResponse::file(fixture("builds/bepinex_be.html")),
+        )
+        .add_dir(GITHUB_DOWNLOADS, dir.join("downloads"));
+    let client = client(&transport);
+
+    let mut github = GitHubApi::new("BepInEx", "BepInEx");
+    github.set_client(client.clone()).set_token(None);
+    let mut builds = BuildsApi::new("https://builds.bepinex.dev");
+    builds.set_client(client.clone());
+    let mut bie = BepInEx::default();
+    bie.register(github).register(builds).fetch();
+    assert!(bie.errors.is_empty(), "{:?}", bie.errors);
+    assert_eq!(bie.releases.len(), 5);
+
+    let game = Game {
+        name: "Pipeline".to_owned(),
+        arch: "x64".to_owned(),
+        path: dir.join("game"),
+        ty: Some(GameType::UnityMono),
+        ..Game::default()
+    };
+    fs::create_dir_all(&game.path).unwrap();
+    let release = bie.latest().unwrap();
+    assert_eq!(release.version, Version::new(5, 4, 21));
+
+    let mut downloader = Downloader::new(client);
+    downloader.set_temp_dir(dir.join("tmp"));
+    let plan = bie.plan_install(&game, &release, &downloader).unwrap();
+    assert_eq!(plan.add.len(), 2);
+    plan.asset.download(&game, &downloader).unwrap();
+    assert!(game.path.join("BepInEx/core/BepInEx.dll").exists());
+
+    let requests = transport.requests();
+    let github = requests
+        .iter()
+        .find(|request| request.url.starts_with(GITHUB_RELEASES))
+        .unwrap();
+    assert_eq!(github.headers[ACCEPT], "application/vnd.github+json");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn recorded_errors_reach_the_caller() {
+    let mut not_found = FixtureResponse::bytes("Not Found");
+    not_found.set_status(StatusCode::NOT_FOUND);
+    let mut transport = FixtureTransport::default();
+    transport.add("https://builds.bepinex.dev/projects/bepinex_be", not_found);
+
+    let mut builds = BuildsApi::new("https://builds.bepinex.dev");
+    builds.set_client(client(&transport));
+    let error = builds.get_builds().unwrap_err();
+    let status = error.downcast_ref::<HttpStatusError>().unwrap();
+    assert_eq!(status.status, StatusCode::NOT_FOUND);
+
+    // Nothing recorded for GitHub
+    let mut github = GitHubApi::new("BepInEx", "BepInEx");
+    github.set_client(client(&transport)).set_token(None);
+    assert!(github
+        .get_all()
+        .unwrap_err()
+        .to_string()
+        .contains("No fixture"));
+}
+
+#[test]
+fn serves_relative_fixture_dirs() {
+    // Tests run from the crate root
+    let mut transport = FixtureTransport::default();
+    transport.add_dir("https://example.com/fixtures", "tests/fixtures");
+
+    let resp = client(&transport)
+        .get("https://example.com/fixtures/github/releases.json")
+        .unwrap();
+    assert_eq!(resp.status, StatusCode::OK);
+    assert_eq!(
+        resp.bytes().unwrap(),
+        fs::read(fixture("github/releases.json")).unwrap()
+    );
+}
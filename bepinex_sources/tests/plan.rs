@@ -0,0 +1,186 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use bepinex_helpers::game::{Game, GameType};
+use bepinex_sources::{
+    bepinex::{AssetDownloader, BepInEx, BepInExAsset, BepInExRelease, ReleaseFlavor},
+    client::SourcesClient,
+    download::Downloader,
+    fixture::{FixtureResponse, FixtureTransport},
+    hash::sha256_hex,
+    plan::{InstallPlan, InstallWarning},
+};
+use reqwest::Url;
+use semver::Version;
+
+use common::{client, proxy, temp_dir, zip};
+
+fn archive() -> Vec<u8> {
+    zip(&[
+        ("winhttp.dll", &proxy(0x8664)),
+        ("doorstop_config.ini", b"[UnityDoorstop]"),
+        ("BepInEx/core/BepInEx.dll", b"core"),
+        ("BepInEx/config/BepInEx.cfg", b"[Logging]"),
+    ])
+}
+
+fn release(link: String, sha256: String) -> BepInExRelease {
+    BepInExRelease {
+        version: Version::new(5, 4, 21),
+        assets: vec![BepInExAsset {
+            name: "BepInEx_x64_5.4.21.0.zip".to_owned(),
+            link,
+            size: None,
+            sha256: Some(sha256),
+        }],
+        flavor: ReleaseFlavor::Stable,
+        source: "local".to_owned(),
+        date: None,
+        branch: None,
+        commits: Vec::new(),
+        notes: None,
+        url: None,
+    }
+}
+
+#[test]
+fn plans_install_without_touching_the_game() {
+    let dir = temp_dir("plan");
+    let bytes = archive();
+    let path = dir.join("BepInEx_x64_5.4.21.0.zip");
+    fs::write(&path, &bytes).unwrap();
+    let release = release(
+        Url::from_file_path(&path).unwrap().to_string(),
+        sha256_hex(&bytes),
+    );
+
+    let game_path = dir.join("game");
+    fs::create_dir_all(game_path.join("BepInEx/core")).unwrap();
+    fs::create_dir_all(game_path.join("BepInEx/config")).unwrap();
+    fs::write(game_path.join("BepInEx/core/BepInEx.dll"), "old core").unwrap();
+    fs::write(game_path.join("BepInEx/core/Removed.dll"), "old").unwrap();
+    fs::write(game_path.join("BepInEx/config/BepInEx.cfg"), "mine").unwrap();
+    let game = Game {
+        name: "Plan".to_owned(),
+        arch: "x64".to_owned(),
+        path: game_path.clone(),
+        ty: Some(GameType::UnityMono),
+        bepinex_version: Some(Version::new(5, 4, 22)),
+        ..Game::default()
+    };
+
+    let bie = BepInEx::default();
+    let downloader = Downloader::new(SourcesClient::default());
+    let plan = bie.plan_install(&game, &release, &downloader).unwrap();
+    assert!(plan.cached);
+    assert_eq!(plan.download_size, bytes.len() as u64);
+    assert_eq!(
+        plan.add,
+        [
+            PathBuf::from("winhttp.dll"),
+            PathBuf::from("doorstop_config.ini")
+        ]
+    );
+    assert_eq!(plan.overwrite, [PathBuf::from("BepInEx/core/BepInEx.dll")]);
+    assert_eq!(plan.remove, [PathBuf::from("BepInEx/core/Removed.dll")]);
+    assert_eq!(plan.preserve, [PathBuf::from("BepInEx/config/BepInEx.cfg")]);
+    assert!(plan.warnings.contains(&InstallWarning::Downgrade {
+        installed: Version::new(5, 4, 22),
+        target: Version::new(5, 4, 21),
+    }));
+    assert!(!game_path.join("winhttp.dll").exists());
+
+    let json = plan.to_json().unwrap();
+    assert_eq!(serde_json::from_str::<InstallPlan>(&json).unwrap(), plan);
+    assert!(plan.to_string().contains("Remove (1):"));
+
+    // Installing without the plan leaves files the archive doesn't have alone
+    let installed = plan.asset.download(&game, &downloader).unwrap();
+    assert_eq!(installed.stale, plan.remove);
+    assert!(game_path.join("BepInEx/core/Removed.dll").exists());
+    plan.prune_stale().unwrap();
+    assert!(!game_path.join("BepInEx/core/Removed.dll").exists());
+    plan.prune_stale().unwrap();
+    let config = fs::read_to_string(game_path.join("BepInEx/config/BepInEx.cfg")).unwrap();
+    assert_eq!(config, "mine");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn installs_the_planned_archive_without_downloading_it_again() {
+    let dir = temp_dir("plan-archive");
+    let bytes = archive();
+    let link = "https://example.com/BepInEx_x64_5.4.21.0.zip";
+    let release = release(link.to_owned(), sha256_hex(&bytes));
+
+    let mut transport = FixtureTransport::default();
+    transport.add(link, FixtureResponse::bytes(bytes));
+    let mut downloader = Downloader::new(client(&transport));
+    downloader.set_temp_dir(dir.join("tmp"));
+
+    let game = Game {
+        name: "Plan".to_owned(),
+        arch: "x64".to_owned(),
+        path: dir.join("game"),
+        ty: Some(GameType::UnityMono),
+        ..Game::default()
+    };
+    fs::create_dir_all(&game.path).unwrap();
+
+    let (plan, archive) = BepInEx::default()
+        .plan_install_with_archive(&game, &release, &downloader)
+        .unwrap();
+    assert!(!plan.cached);
+    let installed = archive.extract(&game).unwrap();
+    assert_eq!(installed.files.len(), plan.add.len());
+    assert!(game.path.join("winhttp.dll").exists());
+    assert_eq!(transport.requests().len(), 1);
+
+    // Downloads without an artifact cache are only kept until the plan is gone
+    let path = archive.path().to_owned();
+    drop(archive);
+    assert!(!path.exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pruning_stays_inside_the_core_folder() {
+    let dir = temp_dir("plan-prune");
+    let game_path = dir.join("game");
+    fs::create_dir_all(game_path.join("BepInEx/core")).unwrap();
+    fs::write(dir.join("outside.txt"), "mine").unwrap();
+    fs::write(game_path.join("BepInEx/core/Stale.dll"), "old").unwrap();
+    fs::write(game_path.join("winhttp.dll"), "proxy").unwrap();
+
+    // Plans can be read back from JSON, so `remove` isn't trusted
+    let plan = InstallPlan {
+        game: "Prune".to_owned(),
+        game_path: game_path.clone(),
+        version: Version::new(5, 4, 21),
+        flavor: ReleaseFlavor::Stable,
+        asset: release(String::new(), String::new()).assets[0].clone(),
+        download_size: 0,
+        cached: true,
+        add: Vec::new(),
+        overwrite: Vec::new(),
+        remove: [
+            "BepInEx/core/Stale.dll",
+            "BepInEx/core/Missing.dll",
+            "winhttp.dll",
+            "BepInEx/core/../../../outside.txt",
+        ]
+        .map(PathBuf::from)
+        .to_vec(),
+        preserve: Vec::new(),
+        warnings: Vec::new(),
+    };
+    plan.prune_stale().unwrap();
+
+    assert!(!game_path.join("BepInEx/core/Stale.dll").exists());
+    assert!(game_path.join("winhttp.dll").exists());
+    assert!(dir.join("outside.txt").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use bepinex_sources::version::ReleaseRequirement;
+
+use common::{bepinex, builds_releases};
+
+fn latest(req: &str) -> Option<usize> {
+    let req: ReleaseRequirement = req.parse().unwrap();
+    bepinex(builds_releases())
+        .latest_matching(&req, None)
+        .and_then(|r| r.artifact_id())
+}
+
+#[test]
+fn matches_bleeding_edge_ranges() {
+    assert_eq!(latest(">=6.0.0-be.650, <6.0.0-be.700"), Some(674));
+    assert_eq!(latest(">=6.0.0-be.500, <6.0.0-be.674"), Some(673));
+    assert_eq!(latest("=6.0.0-be.571"), Some(571));
+    assert_eq!(latest(">=6.0.0-be.700"), None);
+}
+
+#[test]
+fn matches_artifact_ids() {
+    assert_eq!(latest("#673"), Some(673));
+    assert_eq!(latest("latest #571"), Some(571));
+    assert_eq!(latest("#1"), None);
+}
+
+#[test]
+fn stable_requirements_skip_bleeding_edge() {
+    assert_eq!(latest("latest"), None);
+    assert_eq!(latest("*"), None);
+    assert_eq!(latest("^6"), None);
+}
+
+#[test]
+fn parses_requirements() {
+    assert_eq!(
+        "latest ^5.4".parse::<ReleaseRequirement>().unwrap(),
+        "^5.4".parse().unwrap()
+    );
+    assert_eq!(
+        "#674".parse::<ReleaseRequirement>().unwrap(),
+        ReleaseRequirement::Artifact(674)
+    );
+    assert!("not a version".parse::<ReleaseRequirement>().is_err());
+}
@@ -0,0 +1,47 @@
+use bepinex_sources::rules::{Rules, RULES_FORMAT_VERSION};
+
+#[test]
+fn bundled_rules_parse() {
+    let rules = Rules::bundled();
+    assert_eq!(rules.format_version, RULES_FORMAT_VERSION);
+    assert!(!rules.runtimes.is_empty());
+    assert!(!rules.il2cpp_metadata.is_empty());
+}
+
+#[test]
+fn rejects_newer_format() {
+    let json = format!(
+        r#"{{ "format_version": {}, "min_stable_version": "5.4.11" }}"#,
+        RULES_FORMAT_VERSION + 1
+    );
+    assert!(Rules::from_json(&json).is_err());
+}
+
+#[test]
+fn parses_known_bad_and_recommended() {
+    let rules = Rules::from_json(
+        r#"{
+            "format_version": 1,
+            "min_stable_version": "5.4.11",
+            "known_bad": [
+                { "flavor": "bleeding_edge", "artifact_id": 600, "reason": "Crashes on start" }
+            ],
+            "recommended": [
+                { "app_id": 1245620, "version": "5.4.21", "note": "Most mods target 5.4" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(rules.known_bad[0].release.artifact_id, Some(600));
+    assert_eq!(rules.known_bad[0].reason, "Crashes on start");
+    assert_eq!(rules.recommended[0].app_id, 1245620);
+    assert_eq!(
+        rules.recommended[0]
+            .release
+            .version
+            .as_ref()
+            .map(|v| v.to_string()),
+        Some("5.4.21".to_owned())
+    );
+}